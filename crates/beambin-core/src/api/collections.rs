@@ -0,0 +1,208 @@
+//! Responds to collection API requests
+use crate::model::{
+    Collection, CreateCollection, DatabaseError, EditCollection, InsertCollectionPost,
+    RemoveCollectionPost, ReorderCollection,
+};
+
+use crate::ban::check_bans;
+use crate::database::Database;
+use authbeam::model::Profile;
+use axum_extra::extract::CookieJar;
+use databeam::prelude::DefaultReturn;
+
+use axum::{
+    extract::{Path, State},
+    middleware,
+    routing::{get, post},
+    Json, Router,
+};
+
+pub fn routes(database: Database) -> Router {
+    Router::new()
+        .route("/", get(get_collections_request).post(create_request))
+        .route("/{id}", get(get_request))
+        .route("/{id}/edit", post(edit_request))
+        .route("/{id}/delete", post(delete_request))
+        .route("/{id}/insert", post(insert_request))
+        .route("/{id}/remove", post(remove_request))
+        .route("/{id}/reorder", post(reorder_request))
+        // ...
+        .route_layer(middleware::from_fn_with_state(database.clone(), check_bans))
+        .with_state(database)
+}
+
+/// Get the profile of the `__Secure-Token` cookie
+async fn auth_user(jar: &CookieJar, database: &Database) -> Result<Box<Profile>, DatabaseError> {
+    match jar.get("__Secure-Token") {
+        Some(c) => match database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+        {
+            Ok(ua) => Ok(ua),
+            Err(_) => Err(DatabaseError::NotAllowed),
+        },
+        None => Err(DatabaseError::NotAllowed),
+    }
+}
+
+/// Get the collections owned by the current profile (`/api/v1/collections`)
+pub async fn get_collections_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+) -> Result<Json<DefaultReturn<Vec<Collection>>>, DatabaseError> {
+    let auth_user = auth_user(&jar, &database).await?;
+
+    match database.get_collections_by_owner(auth_user.id).await {
+        Ok(c) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Collections exist"),
+            payload: c,
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Create a new collection (`/api/v1/collections`)
+pub async fn create_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Json(props): Json<CreateCollection>,
+) -> Result<Json<DefaultReturn<Collection>>, DatabaseError> {
+    let auth_user = auth_user(&jar, &database).await?;
+
+    match database.create_collection(props, auth_user).await {
+        Ok(c) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Collection created"),
+            payload: c,
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Get an existing collection (`/api/v1/collections/{id}`)
+///
+/// Only the posts the current profile can view are listed.
+pub async fn get_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(id): Path<String>,
+) -> Result<Json<DefaultReturn<Collection>>, DatabaseError> {
+    let auth_user = auth_user(&jar, &database).await.ok();
+
+    let mut collection = database.get_collection(id).await?;
+    collection.posts = database
+        .get_collection_posts(&collection, auth_user.as_deref())
+        .await
+        .into_iter()
+        .map(|p| p.slug)
+        .collect();
+
+    Ok(Json(DefaultReturn {
+        success: true,
+        message: String::from("Collection exists"),
+        payload: collection,
+    }))
+}
+
+/// Change the title of an existing collection (`/api/v1/collections/{id}/edit`)
+pub async fn edit_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(id): Path<String>,
+    Json(props): Json<EditCollection>,
+) -> Result<Json<DefaultReturn<()>>, DatabaseError> {
+    let auth_user = auth_user(&jar, &database).await?;
+
+    match database.edit_collection(id, props, auth_user).await {
+        Ok(_) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Collection updated"),
+            payload: (),
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Delete an existing collection (`/api/v1/collections/{id}/delete`)
+pub async fn delete_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(id): Path<String>,
+) -> Result<Json<DefaultReturn<()>>, DatabaseError> {
+    let auth_user = auth_user(&jar, &database).await?;
+
+    match database.delete_collection(id, auth_user).await {
+        Ok(_) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Collection deleted"),
+            payload: (),
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Add a post to an existing collection (`/api/v1/collections/{id}/insert`)
+pub async fn insert_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(id): Path<String>,
+    Json(props): Json<InsertCollectionPost>,
+) -> Result<Json<DefaultReturn<()>>, DatabaseError> {
+    let auth_user = auth_user(&jar, &database).await?;
+
+    match database.insert_collection_post(id, props, auth_user).await {
+        Ok(_) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Post added"),
+            payload: (),
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Remove a post from an existing collection (`/api/v1/collections/{id}/remove`)
+pub async fn remove_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(id): Path<String>,
+    Json(props): Json<RemoveCollectionPost>,
+) -> Result<Json<DefaultReturn<()>>, DatabaseError> {
+    let auth_user = auth_user(&jar, &database).await?;
+
+    match database
+        .remove_collection_post(id, props.slug, auth_user)
+        .await
+    {
+        Ok(_) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Post removed"),
+            payload: (),
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Change the order of the posts of an existing collection
+/// (`/api/v1/collections/{id}/reorder`)
+pub async fn reorder_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(id): Path<String>,
+    Json(props): Json<ReorderCollection>,
+) -> Result<Json<DefaultReturn<()>>, DatabaseError> {
+    let auth_user = auth_user(&jar, &database).await?;
+
+    match database
+        .reorder_collection(id, props.posts, auth_user)
+        .await
+    {
+        Ok(_) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Collection reordered"),
+            payload: (),
+        })),
+        Err(e) => Err(e),
+    }
+}
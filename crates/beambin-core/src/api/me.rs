@@ -0,0 +1,157 @@
+//! Responds to API requests about the posts of the current profile
+use crate::model::{BulkPosts, DatabaseError, PageQuery, PublicPost, TransferPosts};
+
+use crate::ban::check_bans;
+use crate::database::Database;
+use authbeam::model::Profile;
+use axum_extra::extract::CookieJar;
+use databeam::prelude::DefaultReturn;
+
+use axum::{
+    extract::{Query, State},
+    middleware,
+    routing::{get, post},
+    Json, Router,
+};
+
+pub fn routes(database: Database) -> Router {
+    Router::new()
+        .route("/posts", get(get_posts_request))
+        .route("/posts/delete", post(delete_posts_request))
+        .route("/posts/transfer", post(transfer_posts_request))
+        .route("/posts/export", post(export_posts_request))
+        // ...
+        .route_layer(middleware::from_fn_with_state(database.clone(), check_bans))
+        .with_state(database)
+}
+
+/// Get the profile of the `__Secure-Token` cookie
+async fn auth_user(jar: &CookieJar, database: &Database) -> Result<Box<Profile>, DatabaseError> {
+    match jar.get("__Secure-Token") {
+        Some(c) => match database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+        {
+            Ok(ua) => Ok(ua),
+            Err(_) => Err(DatabaseError::NotAllowed),
+        },
+        None => Err(DatabaseError::NotAllowed),
+    }
+}
+
+/// Get the posts owned by the current profile (`/api/v1/me/posts`)
+pub async fn get_posts_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Query(props): Query<PageQuery>,
+) -> Result<Json<DefaultReturn<Vec<PublicPost>>>, DatabaseError> {
+    let auth_user = auth_user(&jar, &database).await?;
+
+    match database.get_posts_by_owner(auth_user.id, props.page).await {
+        Ok((p, _)) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Posts exist"),
+            payload: p.into_iter().map(PublicPost::from).collect(),
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Delete posts owned by the current profile (`/api/v1/me/posts/delete`)
+///
+/// # Returns
+/// * the slugs of the deleted posts
+pub async fn delete_posts_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Json(props): Json<BulkPosts>,
+) -> Result<Json<DefaultReturn<Vec<String>>>, DatabaseError> {
+    let auth_user = auth_user(&jar, &database).await?;
+    let mut deleted = Vec::new();
+
+    for slug in props.slugs {
+        if database
+            .delete_post(
+                slug.clone(),
+                String::new(),
+                String::new(),
+                Some(auth_user.clone()),
+            )
+            .await
+            .is_ok()
+        {
+            deleted.push(slug);
+        }
+    }
+
+    Ok(Json(DefaultReturn {
+        success: true,
+        message: format!("Deleted {} posts", deleted.len()),
+        payload: deleted,
+    }))
+}
+
+/// Give posts owned by the current profile to another profile (`/api/v1/me/posts/transfer`)
+///
+/// # Returns
+/// * the slugs of the transferred posts
+pub async fn transfer_posts_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Json(props): Json<TransferPosts>,
+) -> Result<Json<DefaultReturn<Vec<String>>>, DatabaseError> {
+    let auth_user = auth_user(&jar, &database).await?;
+
+    // make sure the new owner exists before touching anything
+    if database
+        .auth
+        .get_profile(props.owner.clone())
+        .await
+        .is_err()
+    {
+        return Err(DatabaseError::NotFound);
+    }
+
+    let mut transferred = Vec::new();
+
+    for slug in props.slugs {
+        if database
+            .transfer_post(slug.clone(), props.owner.clone(), auth_user.clone())
+            .await
+            .is_ok()
+        {
+            transferred.push(slug);
+        }
+    }
+
+    Ok(Json(DefaultReturn {
+        success: true,
+        message: format!("Transferred {} posts", transferred.len()),
+        payload: transferred,
+    }))
+}
+
+/// Export posts owned by the current profile (`/api/v1/me/posts/export`)
+pub async fn export_posts_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Json(props): Json<BulkPosts>,
+) -> Result<Json<DefaultReturn<Vec<PublicPost>>>, DatabaseError> {
+    let auth_user = auth_user(&jar, &database).await?;
+    let mut posts = Vec::new();
+
+    for slug in props.slugs {
+        if let Ok(p) = database.get_post(slug).await {
+            if database.is_post_owner(&p, &auth_user) {
+                posts.push(PublicPost::from(p));
+            }
+        }
+    }
+
+    Ok(Json(DefaultReturn {
+        success: true,
+        message: format!("Exported {} posts", posts.len()),
+        payload: posts,
+    }))
+}
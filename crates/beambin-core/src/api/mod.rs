@@ -1,2 +1,6 @@
+pub mod collections;
+pub mod me;
 pub mod posts;
+pub mod reports;
+pub mod tags;
 pub mod util;
@@ -1,58 +1,102 @@
 //! Responds to API requests
 use crate::model::{
-    Post, ClonePost, CreatePost, DeletePost, EditPost, EditContext, DatabaseError, PublicPost,
+    Post, ClonePost, CreatePost, DeletePost, DiffQuery, EditAcl, EditPost, EditContext,
+    DatabaseError, PostDiff, PublicRevision, PostStats, PublicPost, StatsQuery, StatsRequest,
+    TransferPost, CreateShare, Share, ShareQuery, CreateReport, ModeratePost, SearchQuery,
+    ExploreQuery,
 };
 
+use crate::ban::check_bans;
 use crate::database::Database;
-use axum::http::{HeaderMap, HeaderValue};
+use crate::ratelimit::{limit_posts, limit_reports};
+use authbeam::model::Profile;
+use axum::http::{header, HeaderMap, StatusCode};
 use axum_extra::extract::CookieJar;
 use databeam::prelude::DefaultReturn;
+use std::net::SocketAddr;
 
-use axum::response::IntoResponse;
+use axum::response::{IntoResponse, Response};
 use axum::{
-    extract::{Path, State},
+    extract::{ConnectInfo, Path, Query, State},
+    middleware,
     routing::{get, post},
     Json, Router,
 };
 
 pub fn routes(database: Database) -> Router {
     Router::new()
-        .route("/", post(create_request))
-        .route("/clone", post(clone_request))
+        .route(
+            "/",
+            post(create_request)
+                .route_layer(middleware::from_fn_with_state(
+                    database.clone(),
+                    limit_posts,
+                ))
+                // added after the layer, so listing isn't limited like creating
+                .get(list_request),
+        )
+        .route(
+            "/clone",
+            post(clone_request).route_layer(middleware::from_fn_with_state(
+                database.clone(),
+                limit_posts,
+            )),
+        )
+        .route("/search", get(search_request))
         // posts
         .route("/{slug}", get(get_request))
         .route("/{slug}/delete", post(delete_request))
         .route("/{slug}/edit", post(edit_request))
         .route("/{slug}/context", post(edit_post_context))
+        .route("/{slug}/transfer", post(transfer_request))
+        .route("/{slug}/acl", post(acl_request))
+        .route(
+            "/{slug}/report",
+            post(report_request).route_layer(middleware::from_fn_with_state(
+                database.clone(),
+                limit_reports,
+            )),
+        )
+        .route("/{slug}/moderate", post(moderate_request))
+        .route(
+            "/{slug}/shares",
+            get(get_shares_request).post(create_share_request),
+        )
+        .route("/{slug}/shares/{id}/delete", post(delete_share_request))
+        .route("/{slug}/revisions", get(get_revisions_request))
+        .route("/{slug}/revisions/{id}", get(get_revision_request))
+        .route("/{slug}/diff", get(get_diff_request))
+        .route(
+            "/{slug}/stats",
+            get(get_stats_request).post(post_stats_request),
+        )
         // ...
+        .route_layer(middleware::from_fn_with_state(database.clone(), check_bans))
         .with_state(database)
 }
 
 /// Create a new post (`/api/v1/posts`)
 async fn create_request(
+    jar: CookieJar,
     headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(database): State<Database>,
     Json(props): Json<CreatePost>,
 ) -> Result<Json<DefaultReturn<(String, Post)>>, DatabaseError> {
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+            .ok(),
+        None => None,
+    };
+
     // get real ip
-    let real_ip = if let Some(ref real_ip_header) = database.config.real_ip_header {
-        headers
-            .get(real_ip_header.to_owned())
-            .unwrap_or(&HeaderValue::from_static(""))
-            .to_str()
-            .unwrap_or("")
-            .to_string()
-    } else {
-        String::new()
-    };
-
-    // check ip
-    if database.auth.get_ipban_by_ip(real_ip.clone()).await.is_ok() {
-        return Err(DatabaseError::NotAllowed);
-    }
+    let real_ip = database.real_ip(&headers, Some(&addr));
 
     // ...
-    let res = database.create_post(props, real_ip).await;
+    let res = database.create_post(props, real_ip, auth_user).await;
 
     match res {
         Ok(p) => Ok(Json(DefaultReturn {
@@ -66,30 +110,33 @@ async fn create_request(
 
 /// Clone an existing post (`/api/v1/posts/clone`)
 async fn clone_request(
+    jar: CookieJar,
     headers: HeaderMap,
-
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(database): State<Database>,
-    Json(props): Json<ClonePost>,
+    Json(mut props): Json<ClonePost>,
 ) -> impl IntoResponse {
-    // get real ip
-    let real_ip = if let Some(ref real_ip_header) = database.config.real_ip_header {
-        headers
-            .get(real_ip_header.to_owned())
-            .unwrap_or(&HeaderValue::from_static(""))
-            .to_str()
-            .unwrap_or("")
-            .to_string()
-    } else {
-        String::new()
-    };
-
-    // check ip
-    if database.auth.get_ipban_by_ip(real_ip.clone()).await.is_ok() {
-        return Err(DatabaseError::NotAllowed);
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+            .ok(),
+        None => None,
+    };
+
+    // the unlock cookie is named after the source post
+    if let Ok(source) = database.get_post(props.source.clone()).await {
+        if let Some(c) = jar.get(&source.unlock_cookie()) {
+            props.unlock = c.value_trimmed().to_string();
+        }
     }
 
+    // get real ip
+    let real_ip = database.real_ip(&headers, Some(&addr));
+
     // ...
-    let res = database.clone_post(props, real_ip).await;
+    let res = database.clone_post(props, real_ip, auth_user).await;
 
     match res {
         Ok(p) => Ok(Json(DefaultReturn {
@@ -104,6 +151,8 @@ async fn clone_request(
 /// Delete an existing post (`/api/v1/posts/{slug}/delete`)
 async fn delete_request(
     jar: CookieJar,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(database): State<Database>,
     Path(slug): Path<String>,
     Json(props): Json<DeletePost>,
@@ -120,8 +169,14 @@ async fn delete_request(
         None => None,
     };
 
+    // get real ip
+    let real_ip = database.real_ip(&headers, Some(&addr));
+
     // ...
-    match database.delete_post(slug, props.password, auth_user).await {
+    match database
+        .delete_post(slug, real_ip, props.password, auth_user)
+        .await
+    {
         Ok(_) => Ok(Json(DefaultReturn {
             success: true,
             message: String::from("Post deleted"),
@@ -131,10 +186,227 @@ async fn delete_request(
     }
 }
 
+/// Give an existing post to another profile (`/api/v1/posts/{slug}/transfer`)
+async fn transfer_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(slug): Path<String>,
+    Json(props): Json<TransferPost>,
+) -> impl IntoResponse {
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => match database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+        {
+            Ok(ua) => ua,
+            Err(_) => return Err(DatabaseError::NotAllowed),
+        },
+        None => return Err(DatabaseError::NotAllowed),
+    };
+
+    // ...
+    match database.transfer_post(slug, props.owner, auth_user).await {
+        Ok(_) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Post transferred"),
+            payload: (),
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Change a profile's role on an existing post (`/api/v1/posts/{slug}/acl`)
+async fn acl_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(slug): Path<String>,
+    Json(props): Json<EditAcl>,
+) -> impl IntoResponse {
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => match database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+        {
+            Ok(ua) => ua,
+            Err(_) => return Err(DatabaseError::NotAllowed),
+        },
+        None => return Err(DatabaseError::NotAllowed),
+    };
+
+    // ...
+    match database
+        .set_post_role(slug, props.profile, props.role, auth_user)
+        .await
+    {
+        Ok(_) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Access updated"),
+            payload: (),
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Report an existing post to the managers (`/api/v1/posts/{slug}/report`)
+async fn report_request(
+    jar: CookieJar,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(database): State<Database>,
+    Path(slug): Path<String>,
+    Json(props): Json<CreateReport>,
+) -> impl IntoResponse {
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+            .ok(),
+        None => None,
+    };
+
+    // get real ip
+    let real_ip = database.real_ip(&headers, Some(&addr));
+
+    // ...
+    match database
+        .create_report(slug, props, real_ip, auth_user)
+        .await
+    {
+        Ok(_) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Post reported"),
+            payload: (),
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Hide, restore or delete a reported post, or ban its IPs (`/api/v1/posts/{slug}/moderate`)
+async fn moderate_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(slug): Path<String>,
+    Json(props): Json<ModeratePost>,
+) -> impl IntoResponse {
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => match database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+        {
+            Ok(ua) => ua,
+            Err(_) => return Err(DatabaseError::NotAllowed),
+        },
+        None => return Err(DatabaseError::NotAllowed),
+    };
+
+    // ...
+    match database.moderate_post(slug, props, auth_user).await {
+        Ok(_) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Post moderated"),
+            payload: (),
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Get all share links of an existing post (`/api/v1/posts/{slug}/shares`)
+async fn get_shares_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(slug): Path<String>,
+) -> Result<Json<DefaultReturn<Vec<(String, Share)>>>, DatabaseError> {
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => match database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+        {
+            Ok(ua) => ua,
+            Err(_) => return Err(DatabaseError::NotAllowed),
+        },
+        None => return Err(DatabaseError::NotAllowed),
+    };
+
+    // ...
+    match database.get_shares_by_post(slug, auth_user).await {
+        Ok(s) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Shares exist"),
+            payload: s,
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Create a new share link for an existing post (`/api/v1/posts/{slug}/shares`)
+async fn create_share_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(slug): Path<String>,
+    Json(props): Json<CreateShare>,
+) -> Result<Json<DefaultReturn<(String, Share)>>, DatabaseError> {
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => match database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+        {
+            Ok(ua) => ua,
+            Err(_) => return Err(DatabaseError::NotAllowed),
+        },
+        None => return Err(DatabaseError::NotAllowed),
+    };
+
+    // ...
+    match database.create_share(slug, props, auth_user).await {
+        Ok(s) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Share created"),
+            payload: s,
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Revoke a share link of an existing post (`/api/v1/posts/{slug}/shares/{id}/delete`)
+async fn delete_share_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path((slug, id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => match database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+        {
+            Ok(ua) => ua,
+            Err(_) => return Err(DatabaseError::NotAllowed),
+        },
+        None => return Err(DatabaseError::NotAllowed),
+    };
+
+    // ...
+    match database.delete_share(slug, id, auth_user).await {
+        Ok(_) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Share revoked"),
+            payload: (),
+        })),
+        Err(e) => Err(e),
+    }
+}
+
 /// Edit an existing post (`/api/v1/posts/{slug}/edit`)
 async fn edit_request(
     jar: CookieJar,
     headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(database): State<Database>,
     Path(slug): Path<String>,
     Json(props): Json<EditPost>,
@@ -152,16 +424,7 @@ async fn edit_request(
     };
 
     // get real ip
-    let real_ip = if let Some(ref real_ip_header) = database.config.real_ip_header {
-        headers
-            .get(real_ip_header.to_owned())
-            .unwrap_or(&HeaderValue::from_static(""))
-            .to_str()
-            .unwrap_or("")
-            .to_string()
-    } else {
-        String::new()
-    };
+    let real_ip = database.real_ip(&headers, Some(&addr));
 
     // ...
     match database
@@ -172,6 +435,7 @@ async fn edit_request(
             props.new_content,
             props.new_slug,
             props.new_password,
+            props.rewrite_links,
             auth_user,
         )
         .await
@@ -188,6 +452,8 @@ async fn edit_request(
 /// Edit an existing post's context (`/api/v1/posts/{slug}/context`)
 async fn edit_post_context(
     jar: CookieJar,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(database): State<Database>,
     Path(slug): Path<String>,
     Json(props): Json<EditContext>,
@@ -204,9 +470,19 @@ async fn edit_post_context(
         None => None,
     };
 
+    // get real ip
+    let real_ip = database.real_ip(&headers, Some(&addr));
+
     // ...
     match database
-        .edit_post_context(slug, props.password, props.context, auth_user)
+        .edit_post_context(
+            slug,
+            real_ip,
+            props.password,
+            props.context,
+            props.new_view_password,
+            auth_user,
+        )
         .await
     {
         Ok(_) => Ok(Json(DefaultReturn {
@@ -218,28 +494,262 @@ async fn edit_post_context(
     }
 }
 
+/// Check if the post can be viewed through the API, see [`Database::can_view`]
+///
+/// Burn after read posts can't be, as viewing them here wouldn't burn them.
+async fn check_view(
+    database: &Database,
+    jar: &CookieJar,
+    post: &Post,
+    share: &str,
+) -> Result<(), DatabaseError> {
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+            .ok(),
+        None => None,
+    };
+
+    let unlock = jar.get(&post.unlock_cookie()).map(|c| c.value_trimmed());
+
+    database
+        .can_view(post, auth_user.as_deref(), share, unlock)
+        .await?;
+
+    if post.context.burn_after_read {
+        return Err(DatabaseError::NotAllowed);
+    }
+
+    Ok(())
+}
+
 /// Get an existing post by slug (`/api/v1/posts/{slug}`)
 pub async fn get_request(
+    jar: CookieJar,
     State(database): State<Database>,
     Path(slug): Path<String>,
-) -> impl IntoResponse {
-    match database.get_post(slug).await {
+    Query(share): Query<ShareQuery>,
+) -> Response {
+    match database.get_post(slug.clone()).await {
         Ok(p) => {
-            if !p.context.view_password.is_empty() {
-                // cannot view from api if the post has a view password
-                return Err(DatabaseError::Other);
+            if let Err(e) = check_view(&database, &jar, &p, &share.share).await {
+                return e.into_response();
             }
 
-            Ok(Json(DefaultReturn {
+            Json(DefaultReturn {
                 success: true,
                 message: String::from("Post exists"),
                 payload: PublicPost::from(p),
+            })
+            .into_response()
+        }
+        Err(DatabaseError::NotFound) => match database.get_alias(slug).await {
+            // the post was renamed
+            Ok(target) => (
+                StatusCode::MOVED_PERMANENTLY,
+                [(header::LOCATION, format!("/api/v1/posts/{target}"))],
+            )
+                .into_response(),
+            Err(e) => e.into_response(),
+        },
+        Err(e) => e.into_response(),
+    }
+}
+
+/// List public posts (`/api/v1/posts?sort=recent|views`)
+pub async fn list_request(
+    State(database): State<Database>,
+    Query(props): Query<ExploreQuery>,
+) -> Result<Json<DefaultReturn<Vec<PublicPost>>>, DatabaseError> {
+    match database.get_public_posts(props.sort, props.page).await {
+        Ok((p, _)) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Posts exist"),
+            payload: p.into_iter().map(PublicPost::from).collect(),
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Search posts (`/api/v1/posts/search?q=`)
+pub async fn search_request(
+    State(database): State<Database>,
+    Query(props): Query<SearchQuery>,
+) -> Result<Json<DefaultReturn<Vec<PublicPost>>>, DatabaseError> {
+    match database.search_posts(props.q, props.page).await {
+        Ok((p, _)) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Search complete"),
+            payload: p.into_iter().map(PublicPost::from).collect(),
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Get all revisions of an existing post (`/api/v1/posts/{slug}/revisions`)
+pub async fn get_revisions_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(slug): Path<String>,
+    Query(share): Query<ShareQuery>,
+) -> Result<Json<DefaultReturn<Vec<PublicRevision>>>, DatabaseError> {
+    let post = match database.get_post(slug).await {
+        Ok(p) => p,
+        Err(e) => return Err(e),
+    };
+
+    check_view(&database, &jar, &post, &share.share).await?;
+
+    match database.get_revisions_by_post(post.id).await {
+        Ok(r) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Revisions exist"),
+            payload: r.into_iter().map(PublicRevision::from).collect(),
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Get a revision of an existing post (`/api/v1/posts/{slug}/revisions/{id}`)
+pub async fn get_revision_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path((slug, id)): Path<(String, String)>,
+    Query(share): Query<ShareQuery>,
+) -> Result<Json<DefaultReturn<PublicRevision>>, DatabaseError> {
+    let post = match database.get_post(slug).await {
+        Ok(p) => p,
+        Err(e) => return Err(e),
+    };
+
+    check_view(&database, &jar, &post, &share.share).await?;
+
+    match database.get_revision(id).await {
+        Ok(r) => {
+            if r.post != post.id {
+                return Err(DatabaseError::NotFound);
+            }
+
+            Ok(Json(DefaultReturn {
+                success: true,
+                message: String::from("Revision exists"),
+                payload: PublicRevision::from(r),
             }))
         }
         Err(e) => Err(e),
     }
 }
 
+/// Diff two revisions of an existing post (`/api/v1/posts/{slug}/diff`)
+pub async fn get_diff_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(slug): Path<String>,
+    Query(props): Query<DiffQuery>,
+    Query(share): Query<ShareQuery>,
+) -> Result<Json<DefaultReturn<PostDiff>>, DatabaseError> {
+    let post = match database.get_post(slug).await {
+        Ok(p) => p,
+        Err(e) => return Err(e),
+    };
+
+    check_view(&database, &jar, &post, &share.share).await?;
+
+    match database.diff_post(&post, props.from, props.to).await {
+        Ok(d) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Diff generated"),
+            payload: d,
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Get the daily view analytics of an existing post as its owner, an admin or a manager
+/// (`/api/v1/posts/{slug}/stats`)
+pub async fn get_stats_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(slug): Path<String>,
+    Query(props): Query<StatsQuery>,
+) -> Result<Json<DefaultReturn<PostStats>>, DatabaseError> {
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => match database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+        {
+            Ok(ua) => ua,
+            Err(_) => return Err(DatabaseError::NotAllowed),
+        },
+        None => return Err(DatabaseError::NotAllowed),
+    };
+
+    stats_response(&database, slug, Some(&auth_user), "", "", props.days).await
+}
+
+/// Get the daily view analytics of an existing post with its edit password
+/// (`/api/v1/posts/{slug}/stats`)
+pub async fn post_stats_request(
+    jar: CookieJar,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(database): State<Database>,
+    Path(slug): Path<String>,
+    Json(props): Json<StatsRequest>,
+) -> Result<Json<DefaultReturn<PostStats>>, DatabaseError> {
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+            .ok(),
+        None => None,
+    };
+
+    // get real ip
+    let real_ip = database.real_ip(&headers, Some(&addr));
+
+    stats_response(
+        &database,
+        slug,
+        auth_user.as_deref(),
+        &props.password,
+        &real_ip,
+        props.days,
+    )
+    .await
+}
+
+/// Get the daily view analytics of a post, see [`Database::can_view_stats`]
+async fn stats_response(
+    database: &Database,
+    slug: String,
+    user: Option<&Profile>,
+    password: &str,
+    ip: &str,
+    days: u32,
+) -> Result<Json<DefaultReturn<PostStats>>, DatabaseError> {
+    let post = match database.get_post(slug).await {
+        Ok(p) => p,
+        Err(e) => return Err(e),
+    };
+
+    post.check_moderation()?;
+    database.can_view_stats(&post, user, password, ip).await?;
+
+    match database.get_post_stats(post.slug, days).await {
+        Ok(s) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Stats exist"),
+            payload: s,
+        })),
+        Err(e) => Err(e),
+    }
+}
+
 // general
 pub async fn not_found() -> impl IntoResponse {
     Json(DefaultReturn::<u16> {
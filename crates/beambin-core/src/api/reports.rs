@@ -0,0 +1,78 @@
+//! Responds to moderation API requests
+use crate::model::{DatabaseError, PageQuery, PublicPost, Report};
+
+use crate::ban::check_bans;
+use crate::database::Database;
+use authbeam::model::Profile;
+use axum_extra::extract::CookieJar;
+use databeam::prelude::DefaultReturn;
+
+use axum::{
+    extract::{Path, Query, State},
+    middleware,
+    routing::{get, post},
+    Json, Router,
+};
+
+pub fn routes(database: Database) -> Router {
+    Router::new()
+        .route("/", get(get_reports_request))
+        .route("/{id}/delete", post(delete_report_request))
+        // ...
+        .route_layer(middleware::from_fn_with_state(database.clone(), check_bans))
+        .with_state(database)
+}
+
+/// Get the profile of the `__Secure-Token` cookie
+async fn auth_user(jar: &CookieJar, database: &Database) -> Result<Box<Profile>, DatabaseError> {
+    match jar.get("__Secure-Token") {
+        Some(c) => match database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+        {
+            Ok(ua) => Ok(ua),
+            Err(_) => Err(DatabaseError::NotAllowed),
+        },
+        None => Err(DatabaseError::NotAllowed),
+    }
+}
+
+/// Get the report queue (`/api/v1/reports`)
+pub async fn get_reports_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Query(props): Query<PageQuery>,
+) -> Result<Json<DefaultReturn<Vec<(Report, PublicPost)>>>, DatabaseError> {
+    let auth_user = auth_user(&jar, &database).await?;
+
+    match database.get_reports(props.page, auth_user).await {
+        Ok((r, _)) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Reports exist"),
+            payload: r
+                .into_iter()
+                .map(|(report, post)| (report, PublicPost::from(post)))
+                .collect(),
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Dismiss a report (`/api/v1/reports/{id}/delete`)
+pub async fn delete_report_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(id): Path<String>,
+) -> Result<Json<DefaultReturn<()>>, DatabaseError> {
+    let auth_user = auth_user(&jar, &database).await?;
+
+    match database.delete_report(id, auth_user).await {
+        Ok(_) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Report dismissed"),
+            payload: (),
+        })),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,64 @@
+//! Responds to tag API requests
+use crate::model::{DatabaseError, PageQuery, PublicPost, TagCount, TagQuery};
+
+use crate::database::Database;
+use axum_extra::extract::CookieJar;
+use databeam::prelude::DefaultReturn;
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+
+pub fn routes(database: Database) -> Router {
+    Router::new()
+        .route("/", get(suggest_request))
+        .route("/{tag}/posts", get(get_posts_request))
+        // ...
+        .with_state(database)
+}
+
+/// Suggest existing tags (`/api/v1/tags?q=`)
+pub async fn suggest_request(
+    State(database): State<Database>,
+    Query(props): Query<TagQuery>,
+) -> Result<Json<DefaultReturn<Vec<TagCount>>>, DatabaseError> {
+    match database.suggest_tags(props.q).await {
+        Ok(t) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Tags exist"),
+            payload: t,
+        })),
+        Err(e) => Err(e),
+    }
+}
+
+/// Get the posts with a tag (`/api/v1/tags/{tag}/posts`)
+pub async fn get_posts_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Path(tag): Path<String>,
+    Query(props): Query<PageQuery>,
+) -> Result<Json<DefaultReturn<Vec<PublicPost>>>, DatabaseError> {
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+            .ok(),
+        None => None,
+    };
+
+    match database
+        .get_posts_by_tag(tag, props.page, auth_user.as_deref())
+        .await
+    {
+        Ok((p, _)) => Ok(Json(DefaultReturn {
+            success: true,
+            message: String::from("Posts exist"),
+            payload: p.into_iter().map(PublicPost::from).collect(),
+        })),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,49 @@
+//! IP and account ban enforcement
+use crate::database::Database;
+use crate::model::DatabaseError;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum_extra::extract::CookieJar;
+use std::net::SocketAddr;
+
+/// The authbeam group of banned accounts
+pub const BANNED_GROUP: i32 = -1;
+
+/// Reject requests which change something if their IP or account is banned
+///
+/// Requests which only read (`GET`, `HEAD` and `OPTIONS`) are always let through.
+pub async fn check_bans(State(database): State<Database>, req: Request, next: Next) -> Response {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(req).await;
+    }
+
+    // check ip
+    let real_ip = database.real_ip(
+        req.headers(),
+        req.extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr),
+    );
+
+    if !real_ip.is_empty() && database.auth.get_ipban_by_ip(real_ip).await.is_ok() {
+        return DatabaseError::Banned.into_response();
+    }
+
+    // check account
+    if let Some(c) = CookieJar::from_headers(req.headers()).get("__Secure-Token") {
+        if let Ok(ua) = database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+        {
+            if ua.group == BANNED_GROUP {
+                return DatabaseError::Banned.into_response();
+            }
+        }
+    }
+
+    next.run(req).await
+}
@@ -0,0 +1,156 @@
+//! Captcha verification
+use crate::config::Config;
+use crate::model::DatabaseError;
+
+use async_trait::async_trait;
+use authbeam::model::Profile;
+use reqwest::Client as HttpClient;
+use serde::Deserialize;
+
+/// The hCaptcha endpoint used to check captcha responses
+pub const HCAPTCHA_VERIFY_URL: &str = "https://api.hcaptcha.com/siteverify";
+
+/// Something which can check a captcha response
+#[async_trait]
+pub trait CaptchaVerifier: Send + Sync {
+    /// Check if a captcha response is valid
+    ///
+    /// # Arguments
+    /// * `response` - the captcha response sent by the client
+    /// * `ip` - the IP of the client, empty if unknown
+    async fn verify(&self, response: &str, ip: &str) -> bool;
+}
+
+/// [`CaptchaVerifier`] using the hCaptcha service
+pub struct HCaptchaVerifier {
+    pub http: HttpClient,
+    pub secret: String,
+}
+
+#[derive(Deserialize)]
+struct HCaptchaVerifyResponse {
+    success: bool,
+}
+
+#[async_trait]
+impl CaptchaVerifier for HCaptchaVerifier {
+    async fn verify(&self, response: &str, ip: &str) -> bool {
+        let mut form = vec![("secret", self.secret.as_str()), ("response", response)];
+
+        if !ip.is_empty() {
+            form.push(("remoteip", ip));
+        }
+
+        match self.http.post(HCAPTCHA_VERIFY_URL).form(&form).send().await {
+            Ok(res) => match res.json::<HCaptchaVerifyResponse>().await {
+                Ok(body) => body.success,
+                Err(_) => false,
+            },
+            Err(_) => false,
+        }
+    }
+}
+
+/// Check if a user needs to solve a captcha to create a post
+///
+/// # Arguments
+/// * `config` - the server config
+/// * `user` - the user creating the post
+pub fn needs_captcha(config: &Config, user: Option<&Profile>) -> bool {
+    if !config.require_captcha {
+        return false;
+    }
+
+    match user {
+        Some(ua) => ua.group < config.captcha_exempt_group,
+        None => true,
+    }
+}
+
+/// Make sure a user has solved the captcha if they need to
+///
+/// # Arguments
+/// * `verifier` - the [`CaptchaVerifier`]
+/// * `config` - the server config
+/// * `response` - the captcha response sent by the client
+/// * `ip` - the IP of the client
+/// * `user` - the user creating the post
+pub async fn check_captcha(
+    verifier: &dyn CaptchaVerifier,
+    config: &Config,
+    response: &str,
+    ip: &str,
+    user: Option<&Profile>,
+) -> Result<(), DatabaseError> {
+    if !needs_captcha(config, user) {
+        return Ok(());
+    }
+
+    if response.is_empty() || !verifier.verify(response, ip).await {
+        return Err(DatabaseError::CaptchaFailed);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Accepts only the response `"ok"`
+    struct FakeVerifier;
+
+    #[async_trait]
+    impl CaptchaVerifier for FakeVerifier {
+        async fn verify(&self, response: &str, _: &str) -> bool {
+            response == "ok"
+        }
+    }
+
+    fn config() -> Config {
+        Config {
+            require_captcha: true,
+            captcha_exempt_group: 1,
+            ..Config::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn anonymous_users_solve_captcha() {
+        let config = config();
+
+        assert!(check_captcha(&FakeVerifier, &config, "ok", "", None)
+            .await
+            .is_ok());
+        assert!(check_captcha(&FakeVerifier, &config, "wrong", "", None)
+            .await
+            .is_err());
+        assert!(check_captcha(&FakeVerifier, &config, "", "", None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn exempt_group_skips_captcha() {
+        let config = config();
+
+        let mut user = Profile::default();
+        assert!(check_captcha(&FakeVerifier, &config, "", "", Some(&user))
+            .await
+            .is_err());
+
+        user.group = 1;
+        assert!(check_captcha(&FakeVerifier, &config, "", "", Some(&user))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn captcha_not_required() {
+        assert!(
+            check_captcha(&FakeVerifier, &Config::default(), "", "", None)
+                .await
+                .is_ok()
+        );
+    }
+}
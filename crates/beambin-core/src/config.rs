@@ -67,6 +67,194 @@ impl Default for ViewsConfig {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ViewCountsConfig {
+    /// The name of the table
+    pub table_name: String,
+}
+
+impl Default for ViewCountsConfig {
+    fn default() -> Self {
+        Self {
+            table_name: "view_counts".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StatsConfig {
+    /// The name of the table
+    pub table_name: String,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self {
+            table_name: "stats".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RevisionsConfig {
+    /// The name of the table
+    pub table_name: String,
+    /// The caching prefix associated with the table
+    pub prefix: String,
+}
+
+impl Default for RevisionsConfig {
+    fn default() -> Self {
+        Self {
+            table_name: "revisions".to_string(),
+            prefix: "pb.revision".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AliasesConfig {
+    /// The name of the table
+    pub table_name: String,
+}
+
+impl Default for AliasesConfig {
+    fn default() -> Self {
+        Self {
+            table_name: "aliases".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SharesConfig {
+    /// The name of the table
+    pub table_name: String,
+}
+
+impl Default for SharesConfig {
+    fn default() -> Self {
+        Self {
+            table_name: "shares".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReportsConfig {
+    /// The name of the table
+    pub table_name: String,
+}
+
+impl Default for ReportsConfig {
+    fn default() -> Self {
+        Self {
+            table_name: "reports".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SearchConfig {
+    /// The name of the table (the full-text index of searchable posts)
+    pub table_name: String,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            table_name: "search".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TagsConfig {
+    /// The name of the table (the `(tag, post)` pairs of every tagged post)
+    pub table_name: String,
+}
+
+impl Default for TagsConfig {
+    fn default() -> Self {
+        Self {
+            table_name: "tags".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CollectionsConfig {
+    /// The name of the table
+    pub table_name: String,
+}
+
+impl Default for CollectionsConfig {
+    fn default() -> Self {
+        Self {
+            table_name: "collections".to_string(),
+        }
+    }
+}
+
+/// A rate limit budget, a token bucket
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RateLimit {
+    /// The number of tokens in a full bucket (the largest burst of requests), `0` disables
+    /// the limit
+    pub capacity: u32,
+    /// The number of tokens put back in the bucket every minute
+    ///
+    /// Every request takes a token, so once the bucket is empty requests are allowed at
+    /// this rate until a break lets it fill up again.
+    pub per_minute: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RateLimitsConfig {
+    /// Creating and cloning posts
+    #[serde(default = "RateLimitsConfig::default_posts")]
+    pub posts: RateLimit,
+    /// Rendering markdown previews
+    #[serde(default = "RateLimitsConfig::default_render")]
+    pub render: RateLimit,
+    /// Reporting posts
+    #[serde(default = "RateLimitsConfig::default_reports")]
+    pub reports: RateLimit,
+}
+
+impl RateLimitsConfig {
+    fn default_posts() -> RateLimit {
+        RateLimit {
+            capacity: 10,
+            per_minute: 5,
+        }
+    }
+
+    fn default_render() -> RateLimit {
+        RateLimit {
+            capacity: 60,
+            per_minute: 60,
+        }
+    }
+
+    fn default_reports() -> RateLimit {
+        RateLimit {
+            capacity: 5,
+            per_minute: 2,
+        }
+    }
+}
+
+impl Default for RateLimitsConfig {
+    fn default() -> Self {
+        Self {
+            posts: Self::default_posts(),
+            render: Self::default_render(),
+            reports: Self::default_reports(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Config {
     /// The port to serve the server on
@@ -79,6 +267,8 @@ pub struct Config {
     #[serde(default)]
     pub static_dir: PathBufD,
     /// The name of the header used for reading user IP address
+    ///
+    /// Requests without it use the address of their connection instead.
     pub real_ip_header: Option<String>,
     /// The origin of the public server
     ///
@@ -95,25 +285,77 @@ pub struct Config {
     /// The slug of the server's information post
     #[serde(default)]
     pub info_post_slug: String,
+    /// If the homepage shows the post at `info_post_slug` below the editor
+    #[serde(default)]
+    pub homepage_info_post: bool,
+    /// If the homepage shows the most recent public posts below the editor
+    #[serde(default)]
+    pub homepage_recent_posts: bool,
     /// If posts can require a password to be viewed
     #[serde(default)]
     pub view_password: bool,
-    /// If posts can have a owner username
+    /// If posts can have an owner
     ///
-    /// Not implemented.
+    /// Posts created while logged in are owned by their creator, and their owner can
+    /// manage them without the edit password.
     #[serde(default)]
     pub post_ownership: bool,
     /// View mode options
     #[serde(default)]
     pub view_mode: ViewMode,
+    /// The key used to sign share links and unlock cookies, required
+    ///
+    /// One is generated when the config file is first written. Every server sharing a
+    /// database needs the same one, and changing it invalidates every existing share link.
+    #[serde(default)]
+    pub secret: String,
     /// HCaptcha configuration
     pub captcha: HCaptchaConfig,
+    /// If creating or cloning a post needs a solved captcha
+    #[serde(default)]
+    pub require_captcha: bool,
+    /// Logged in users in this group or above don't need to solve a captcha
+    #[serde(default)]
+    pub captcha_exempt_group: i32,
+    /// Rate limits of each route group
+    ///
+    /// Requests are counted per profile, or per IP for anonymous users (from
+    /// `real_ip_header`, or the address of their connection without it).
+    #[serde(default)]
+    pub rate_limits: RateLimitsConfig,
     /// Posts table config
     #[serde(default)]
     pub table_posts: PostsConfig,
     /// Views table config
     #[serde(default)]
     pub table_views: ViewsConfig,
+    /// View counts table config
+    #[serde(default)]
+    pub table_view_counts: ViewCountsConfig,
+    /// Daily view stats table config
+    #[serde(default)]
+    pub table_stats: StatsConfig,
+    /// Revisions table config
+    #[serde(default)]
+    pub table_revisions: RevisionsConfig,
+    /// Aliases (old slugs of renamed posts) table config
+    #[serde(default)]
+    pub table_aliases: AliasesConfig,
+    /// Shares (signed links to posts) table config
+    #[serde(default)]
+    pub table_shares: SharesConfig,
+    /// Reports (of abusive posts) table config
+    #[serde(default)]
+    pub table_reports: ReportsConfig,
+    /// Search index table config
+    #[serde(default)]
+    pub table_search: SearchConfig,
+    /// Tags table config
+    #[serde(default)]
+    pub table_tags: TagsConfig,
+    /// Collections table config
+    #[serde(default)]
+    pub table_collections: CollectionsConfig,
     /// The location of media uploads on the file system
     #[serde(default)]
     pub media_dir: PathBufD,
@@ -134,12 +376,27 @@ impl Config {
             blocked_hosts: Vec::new(),
             real_ip_header: None,
             info_post_slug: String::new(),
+            homepage_info_post: false,
+            homepage_recent_posts: false,
             view_password: true,
             post_ownership: true,
             view_mode: ViewMode::OpenMultiple,
+            secret: String::new(),
             captcha: HCaptchaConfig::default(),
+            require_captcha: false,
+            captcha_exempt_group: 0,
+            rate_limits: RateLimitsConfig::default(),
             table_posts: PostsConfig::default(),
             table_views: ViewsConfig::default(),
+            table_view_counts: ViewCountsConfig::default(),
+            table_stats: StatsConfig::default(),
+            table_revisions: RevisionsConfig::default(),
+            table_aliases: AliasesConfig::default(),
+            table_shares: SharesConfig::default(),
+            table_reports: ReportsConfig::default(),
+            table_search: SearchConfig::default(),
+            table_tags: TagsConfig::default(),
+            table_collections: CollectionsConfig::default(),
             media_dir: PathBufD::new(),
             snowflake_server_id: 1234567890,
         }
@@ -158,12 +415,27 @@ impl Default for Config {
             blocked_hosts: Vec::new(),
             real_ip_header: None,
             info_post_slug: String::new(),
+            homepage_info_post: false,
+            homepage_recent_posts: false,
             view_password: false,
             post_ownership: false,
             view_mode: ViewMode::OpenMultiple,
+            secret: String::new(),
             captcha: HCaptchaConfig::default(),
+            require_captcha: false,
+            captcha_exempt_group: 0,
+            rate_limits: RateLimitsConfig::default(),
             table_posts: PostsConfig::default(),
             table_views: ViewsConfig::default(),
+            table_view_counts: ViewCountsConfig::default(),
+            table_stats: StatsConfig::default(),
+            table_revisions: RevisionsConfig::default(),
+            table_aliases: AliasesConfig::default(),
+            table_shares: SharesConfig::default(),
+            table_reports: ReportsConfig::default(),
+            table_search: SearchConfig::default(),
+            table_tags: TagsConfig::default(),
+            table_collections: CollectionsConfig::default(),
             media_dir: PathBufD::new(),
             snowflake_server_id: 1234567890,
         }
@@ -181,13 +453,24 @@ impl Config {
         let c = fs::canonicalize(".").unwrap();
         let here = c.to_str().unwrap();
 
-        match fs::read(format!("{here}/.config/config.toml")) {
+        let config = match fs::read(format!("{here}/.config/config.toml")) {
             Ok(c) => Config::read(c),
             Err(_) => {
-                Self::update_config(Self::default()).expect("failed to write default config");
-                Self::default()
+                let config = Self {
+                    secret: databeam::utility::random_id(),
+                    ..Self::default()
+                };
+
+                Self::update_config(config.clone()).expect("failed to write default config");
+                config
             }
+        };
+
+        if config.secret.is_empty() {
+            panic!("`secret` must be set in .config/config.toml");
         }
+
+        config
     }
 
     /// Update configuration file
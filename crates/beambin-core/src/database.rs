@@ -1,14 +1,121 @@
-use crate::model::{CreatePost, DatabaseError, Post, PostContext, ClonePost, ViewMode};
+use crate::model::{
+    AclRole, CreatePost, CreateShare, Share, SharePermission, DatabaseError, Post, PostContext,
+    PostDayStats, PostDiff, PostRevision, PostStats, ClonePost, ViewMode, CreateReport, ModAction,
+    ModeratePost, Moderation, Report, PostSort, Visibility, TagCount, Collection, CreateCollection,
+    EditCollection, InsertCollectionPost,
+};
+use crate::captcha::{check_captcha, CaptchaVerifier, HCaptchaVerifier};
 use crate::config::Config;
+use crate::password;
+use crate::ratelimit::{self, RateLimitGroup, RateLimitStatus};
+use crate::search;
+use crate::stats::HyperLogLog;
+use crate::tags;
 
-use authbeam::model::Profile;
+use authbeam::model::{DatabaseError as AuthError, IpBanCreate, Profile};
+use axum::http::HeaderMap;
 use reqwest::Client as HttpClient;
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 
 use databeam::utility;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
 use databeam::{query as sqlquery, prelude::*};
 
 pub type Result<T> = std::result::Result<T, DatabaseError>;
 
+/// Daily `(views, visitors)` of posts by `(slug, day)`
+pub type PendingStats = HashMap<(String, u128), (i32, HyperLogLog)>;
+
+/// The number of posts on each page of [`Database::get_posts_by_owner`]
+pub const POSTS_PER_PAGE: u32 = 25;
+
+/// Get the offset of the first row of a page
+///
+/// # Arguments
+/// * `page` - the page number
+pub fn page_offset(page: u32) -> u64 {
+    u64::from(page) * u64::from(POSTS_PER_PAGE)
+}
+
+/// The most posts a [`Collection`] can have
+pub const MAX_COLLECTION_POSTS: usize = 500;
+/// The number of wrong passwords allowed before a post (or IP) is locked
+pub const FAILURES_BEFORE_LOCK: u32 = 5;
+/// How long (in milliseconds) the first lock lasts, every wrong password after it doubles it
+pub const LOCK_DURATION: u128 = 30 * 1000;
+/// The longest (in milliseconds) a lock can last
+pub const MAX_LOCK_DURATION: u128 = 24 * 60 * 60 * 1000;
+/// How long (in milliseconds) wrong passwords are remembered for while not locked
+pub const FAILURE_WINDOW: u128 = 60 * 60 * 1000;
+
+/// How long (in milliseconds) entering a post's view password unlocks it for
+pub const UNLOCK_DURATION: u128 = 60 * 60 * 1000;
+
+/// The length of a day in milliseconds
+const DAY: u128 = 86_400_000;
+
+/// A wrong password counter of a post or IP
+///
+/// Stored in the cache as one number, `since * 1000 + failures`, so a wrong password is
+/// counted with a single atomic `incr`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PasswordFailures {
+    /// The time of the first remembered wrong password, or of the last lock
+    since: u128,
+    /// The number of wrong passwords (at most `999`)
+    failures: u128,
+}
+
+impl PasswordFailures {
+    /// Read a counter from its cached value
+    fn decode(value: u128) -> Self {
+        Self {
+            since: value / 1000,
+            failures: value % 1000,
+        }
+    }
+
+    /// Get the cached value of the counter
+    fn encode(&self) -> String {
+        (self.since * 1000 + self.failures.min(999)).to_string()
+    }
+
+    /// The time the counter is locked until, `0` if it isn't locked
+    fn locked_until(&self) -> u128 {
+        let Some(over) = self.failures.checked_sub(u128::from(FAILURES_BEFORE_LOCK)) else {
+            return 0;
+        };
+
+        self.since + (LOCK_DURATION << over.min(32)).min(MAX_LOCK_DURATION)
+    }
+
+    /// Check if the counter is unlocked and older than [`FAILURE_WINDOW`]
+    ///
+    /// # Arguments
+    /// * `now` - the current time
+    fn is_stale(&self, now: u128) -> bool {
+        (self.locked_until() <= now) && (now.saturating_sub(self.since) > FAILURE_WINDOW)
+    }
+}
+
+/// Slugs taken by the routes of the server, posts with them could never be viewed
+pub const RESERVED_SLUGS: &[&str] = &[
+    "api",
+    "c",
+    "dashboard",
+    "explore",
+    "feed.json",
+    "feed.xml",
+    "mod",
+    "search",
+    "static",
+    "tag",
+];
+
 /// Database connector
 #[derive(Clone)]
 pub struct Database {
@@ -16,6 +123,12 @@ pub struct Database {
     pub auth: authbeam::Database,
     pub config: Config,
     pub http: HttpClient,
+    /// The [`CaptchaVerifier`] used for new posts
+    pub captcha: Arc<dyn CaptchaVerifier>,
+    /// Views which haven't been written to the database yet, by slug
+    pub pending_views: Arc<Mutex<HashMap<String, i32>>>,
+    /// Daily stats which haven't been written to the database yet, by (slug, day)
+    pub pending_stats: Arc<Mutex<PendingStats>>,
 }
 
 impl Database {
@@ -25,12 +138,57 @@ impl Database {
         auth: authbeam::Database,
         config: Config,
     ) -> Self {
+        let http = HttpClient::new();
+
+        // never sign anything with an empty (or per instance) key
+        assert!(
+            !config.secret.is_empty(),
+            "`secret` must be set in the config"
+        );
+
         Self {
             base: StarterDatabase::new(database_options).await,
             auth,
+            captcha: Arc::new(HCaptchaVerifier {
+                http: http.clone(),
+                secret: config.captcha.secret.clone(),
+            }),
             config,
-            http: HttpClient::new(),
+            http,
+            pending_views: Arc::new(Mutex::new(HashMap::new())),
+            pending_stats: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Replace the [`CaptchaVerifier`] (the hCaptcha service by default)
+    pub fn with_captcha_verifier(mut self, verifier: Arc<dyn CaptchaVerifier>) -> Self {
+        self.captcha = verifier;
+        self
+    }
+
+    /// Get the IP address of a request, from `real_ip_header` if it's set (and given)
+    ///
+    /// Requests without the header are counted under their socket address, so they never
+    /// all share the same blank IP.
+    ///
+    /// # Arguments
+    /// * `headers` - the headers of the request
+    /// * `addr` - the socket address the request came from (needs the server to be started
+    ///   with `into_make_service_with_connect_info`)
+    pub fn real_ip(&self, headers: &HeaderMap, addr: Option<&SocketAddr>) -> String {
+        let header = match self.config.real_ip_header {
+            Some(ref real_ip_header) => headers
+                .get(real_ip_header)
+                .and_then(|h| h.to_str().ok())
+                .unwrap_or(""),
+            None => "",
+        };
+
+        if !header.is_empty() {
+            return header.to_string();
         }
+
+        addr.map(|a| a.ip().to_string()).unwrap_or_default()
     }
 
     /// Init database
@@ -75,11 +233,183 @@ impl Database {
             ))
             .execute(c)
             .await;
+
+            // a user's view can only be logged once
+            let _ = sqlquery(&unique_index_query(
+                &self.base.db.r#type,
+                &self.config.table_views.table_name,
+                &["slug", "id"],
+            ))
+            .execute(c)
+            .await;
+        }
+
+        if self.config.view_mode == ViewMode::OpenMultiple {
+            // create table to store view counts
+            let _ = sqlquery(&format!(
+                "CREATE TABLE IF NOT EXISTS \"{}\" (
+                    slug     TEXT,
+                    count    TEXT
+                )",
+                self.config.table_view_counts.table_name
+            ))
+            .execute(c)
+            .await;
+
+            let _ = sqlquery(&unique_index_query(
+                &self.base.db.r#type,
+                &self.config.table_view_counts.table_name,
+                &["slug"],
+            ))
+            .execute(c)
+            .await;
+        }
+
+        let _ = sqlquery(&format!(
+            "CREATE TABLE IF NOT EXISTS \"{}\" (
+                slug     TEXT,
+                day      TEXT,
+                views    TEXT,
+                visitors TEXT
+            )",
+            self.config.table_stats.table_name
+        ))
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(&format!(
+            "CREATE TABLE IF NOT EXISTS \"{}\" (
+                slug   TEXT,
+                target TEXT
+            )",
+            self.config.table_aliases.table_name
+        ))
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(&unique_index_query(
+            &self.base.db.r#type,
+            &self.config.table_aliases.table_name,
+            &["slug"],
+        ))
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(&format!(
+            "CREATE TABLE IF NOT EXISTS \"{}\" (
+                id         TEXT,
+                post       TEXT,
+                permission TEXT,
+                timestamp  TEXT,
+                expires_at TEXT
+            )",
+            self.config.table_shares.table_name
+        ))
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(&format!(
+            "CREATE TABLE IF NOT EXISTS \"{}\" (
+                id        TEXT,
+                post      TEXT,
+                reason    TEXT,
+                reporter  TEXT,
+                ip        TEXT,
+                timestamp TEXT
+            )",
+            self.config.table_reports.table_name
+        ))
+        .execute(c)
+        .await;
+
+        for query in
+            search::create_index_queries(&self.base.db.r#type, &self.config.table_search.table_name)
+        {
+            let _ = sqlquery(&query).execute(c).await;
         }
+
+        let _ = sqlquery(&format!(
+            "CREATE TABLE IF NOT EXISTS \"{}\" (
+                id        TEXT,
+                post      TEXT,
+                timestamp TEXT,
+                editor    TEXT,
+                content   TEXT,
+                context   TEXT
+            )",
+            self.config.table_revisions.table_name
+        ))
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(&format!(
+            "CREATE TABLE IF NOT EXISTS \"{}\" (
+                tag  TEXT,
+                post TEXT
+            )",
+            self.config.table_tags.table_name
+        ))
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(&format!(
+            "CREATE TABLE IF NOT EXISTS \"{}\" (
+                id           TEXT,
+                title        TEXT,
+                owner        TEXT,
+                posts        TEXT,
+                date_created TEXT,
+                date_edited  TEXT
+            )",
+            self.config.table_collections.table_name
+        ))
+        .execute(c)
+        .await;
+
+        // index the posts created before search existed
+        let _ = self.backfill_search_index().await;
     }
 
     // ...
 
+    /// Create a [`Post`] from a database row
+    ///
+    /// # Arguments
+    /// * `res` - the row
+    fn gimme_post(&self, res: BTreeMap<String, String>) -> Result<Post> {
+        Ok(Post {
+            id: res.get(&self.config.table_posts.id).unwrap().to_string(),
+            slug: res.get(&self.config.table_posts.slug).unwrap().to_string(),
+            password: res
+                .get(&self.config.table_posts.password)
+                .unwrap()
+                .to_string(),
+            content: res
+                .get(&self.config.table_posts.content)
+                .unwrap()
+                .to_string(),
+            date_published: res
+                .get(&self.config.table_posts.date_published)
+                .unwrap()
+                .parse::<u128>()
+                .unwrap(),
+            date_edited: res
+                .get(&self.config.table_posts.date_edited)
+                .unwrap()
+                .parse::<u128>()
+                .unwrap(),
+            context: match serde_json::from_str(res.get(&self.config.table_posts.context).unwrap())
+            {
+                Ok(m) => m,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            ips: match serde_json::from_str(res.get(&self.config.table_posts.ips).unwrap()) {
+                Ok(m) => m,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+        })
+    }
+
     /// Get an existing post
     ///
     /// # Arguments
@@ -98,7 +428,16 @@ impl Database {
             .get(format!("{}:{}", self.config.table_posts.prefix, slug))
             .await
         {
-            Some(c) => return Ok(serde_json::from_str::<Post>(c.as_str()).unwrap()),
+            Some(c) => {
+                let post = serde_json::from_str::<Post>(c.as_str()).unwrap();
+
+                if post.is_expired() {
+                    let _ = self.remove_post(&post).await;
+                    return Err(DatabaseError::NotFound);
+                }
+
+                return Ok(post);
+            }
             None => (),
         };
 
@@ -124,37 +463,14 @@ impl Database {
         };
 
         // return
-        let post = Post {
-            id: res.get(&self.config.table_posts.id).unwrap().to_string(),
-            slug: res.get(&self.config.table_posts.slug).unwrap().to_string(),
-            password: res
-                .get(&self.config.table_posts.password)
-                .unwrap()
-                .to_string(),
-            content: res
-                .get(&self.config.table_posts.content)
-                .unwrap()
-                .to_string(),
-            date_published: res
-                .get(&self.config.table_posts.date_published)
-                .unwrap()
-                .parse::<u128>()
-                .unwrap(),
-            date_edited: res
-                .get(&self.config.table_posts.date_edited)
-                .unwrap()
-                .parse::<u128>()
-                .unwrap(),
-            context: match serde_json::from_str(res.get(&self.config.table_posts.context).unwrap())
-            {
-                Ok(m) => m,
-                Err(_) => return Err(DatabaseError::ValueError),
-            },
-            ips: match serde_json::from_str(res.get(&self.config.table_posts.ips).unwrap()) {
-                Ok(m) => m,
-                Err(_) => return Err(DatabaseError::ValueError),
-            },
-        };
+        let post = self.gimme_post(res)?;
+
+        // expired posts are removed as soon as they're requested,
+        // the rest are removed by `purge_expired_posts`
+        if post.is_expired() {
+            let _ = self.remove_post(&post).await;
+            return Err(DatabaseError::NotFound);
+        }
 
         // store in cache
         self.base
@@ -169,112 +485,602 @@ impl Database {
         Ok(post)
     }
 
-    /// Create a new post
+    /// Get an existing post by its `id` (bypassing the cache)
     ///
     /// # Arguments
-    /// * `props` - [`PostCreate`]
-    /// * `ip` - the IP address where this post was created
-    ///
-    /// # Returns
-    /// * Result containing a tuple with the unhashed edit password and the post
-    pub async fn create_post(&self, mut props: CreatePost, ip: String) -> Result<(String, Post)> {
-        props.slug = idna::punycode::encode_str(&props.slug)
-            .unwrap()
-            .to_lowercase();
-
-        if props.slug.ends_with("-") {
-            props.slug.pop();
+    /// * `id` - the ID of the post
+    pub async fn get_post_by_id(&self, id: String) -> Result<Post> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \":t\" WHERE \":id\" = ?"
+        } else {
+            "SELECT * FROM \":t\" WHERE \":id\" = $1"
         }
+        .to_string()
+        .replace(":t", &self.config.table_posts.table_name)
+        .replace(":id", &self.config.table_posts.id);
 
-        // make sure post doesn't already exist
-        if let Ok(_) = self.get_post(props.slug.clone()).await {
-            return Err(DatabaseError::AlreadyExists);
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&id).fetch_one(c).await {
+            Ok(p) => self.gimme_post(self.base.textify_row(p).0),
+            Err(_) => Err(DatabaseError::NotFound),
         }
+    }
 
-        // create slug if not supplied
-        if props.slug.is_empty() {
-            props.slug = utility::random_id().chars().take(10).collect();
-        }
+    /// Check if a profile owns a post, always `false` when `post_ownership` is disabled
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `user` - the profile
+    pub fn is_post_owner(&self, post: &Post, user: &Profile) -> bool {
+        self.config.post_ownership
+            && !post.context.owner.is_empty()
+            && (post.context.owner == user.id)
+    }
 
-        // create random password if not supplied
-        if props.password.is_empty() {
-            props.password = utility::random_id().chars().take(10).collect();
+    /// Get the role a profile has on a post, owners are always admins
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `user` - the profile
+    pub fn get_post_role(&self, post: &Post, user: &Profile) -> Option<AclRole> {
+        if self.is_post_owner(post, user) {
+            return Some(AclRole::Admin);
         }
 
-        // check lengths
-        if (props.slug.len() > 250) | (props.slug.len() < 3) {
-            return Err(DatabaseError::ValueError);
-        }
+        post.context.acl.get(&user.id).copied()
+    }
 
-        if (props.content.len() > 200_000) | (props.content.len() < 1) {
-            return Err(DatabaseError::ValueError);
+    /// Check if a profile has at least the given role on a post
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `user` - the profile
+    /// * `role` - the required role
+    pub fn has_post_role(&self, post: &Post, user: &Profile, role: AclRole) -> bool {
+        self.get_post_role(post, user).is_some_and(|r| r >= role)
+    }
+
+    /// Check if a profile (or a visitor without one) can view a post with its visibility,
+    /// private posts are only viewable by profiles on their access list
+    ///
+    /// Share links are checked separately, see [`Database::check_share`].
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `user` - the profile viewing the post
+    pub fn can_view_post(&self, post: &Post, user: Option<&Profile>) -> bool {
+        match post.context.visibility {
+            Visibility::Private => {
+                user.is_some_and(|ua| self.has_post_role(post, ua, AclRole::Viewer))
+            }
+            _ => true,
         }
+    }
 
-        // (characters used)
-        let regex = regex::RegexBuilder::new("^[\\w\\_\\-\\.\\!\\p{Extended_Pictographic}]+$")
-            .multi_line(true)
-            .build()
-            .unwrap();
+    /// Check if a profile (or a visitor without one) can view a post, this is the one check
+    /// used everywhere a post is shown
+    ///
+    /// Profiles on the access list and share link holders don't need the view password,
+    /// everyone else needs a token from [`Database::unlock_post_view`].
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `user` - the profile viewing the post
+    /// * `share` - the share link token given with the request (blank if none)
+    /// * `unlock` - the token from the post's [`Post::unlock_cookie`], if any
+    ///
+    /// # Returns
+    /// * the permission of the share link, if one was given
+    /// * `DatabaseError::NotFound` if the post is private and can't be viewed by `user`
+    /// * `DatabaseError::PasswordIncorrect` if the view password is needed
+    pub async fn can_view(
+        &self,
+        post: &Post,
+        user: Option<&Profile>,
+        share: &str,
+        unlock: Option<&str>,
+    ) -> Result<Option<SharePermission>> {
+        post.check_moderation()?;
 
-        if regex.captures(&props.slug).iter().len() < 1 {
-            return Err(DatabaseError::ValueError);
+        let share = self.check_share(post, share).await;
+
+        // private posts are only viewable by their access list and share link holders
+        if share.is_none() && !self.can_view_post(post, user) {
+            return Err(DatabaseError::NotFound);
         }
 
-        // ...
-        let post = Post {
-            id: utility::random_id(),
-            slug: props.slug,
-            content: props.content,
-            password: utility::hash(props.password.clone()),
-            date_published: utility::unix_epoch_timestamp(),
-            date_edited: utility::unix_epoch_timestamp(),
-            context: PostContext::default(),
-            ips: vec![(utility::unix_epoch_timestamp(), ip)],
-        };
+        let is_viewer =
+            share.is_some() | user.is_some_and(|ua| self.has_post_role(post, ua, AclRole::Viewer));
 
-        // create post
-        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        let unlocked = unlock.is_some_and(|token| self.check_unlock(post, token));
+
+        if needs_view_password(post, self.config.view_password, is_viewer, unlocked) {
+            return Err(DatabaseError::PasswordIncorrect);
+        }
+
+        Ok(share)
+    }
+
+    /// Check the edit password of a post, edit share links are accepted in its place
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `password` - the edit password or share link token
+    /// * `ip` - the IP address of the user giving the password
+    ///
+    /// # Returns
+    /// * `Some(permission)` if the post was unlocked by a share link
+    /// * `DatabaseError::TooManyAttempts` if the post or IP is locked
+    pub async fn unlock_post(
+        &self,
+        post: &Post,
+        password: &str,
+        ip: &str,
+    ) -> Result<Option<SharePermission>> {
+        let slug_key = self.failures_key("slug", &post.slug);
+        let ip_key = self.ip_failures_key(ip);
+
+        self.check_password_locks(&slug_key, ip_key.as_deref())
+            .await?;
+
+        // check password
+        if password::verify(password, &post.password) {
+            self.clear_password_failures(&slug_key, ip_key.as_deref())
+                .await;
+
+            return Ok(None);
+        }
+
+        if let Some(SharePermission::Edit) = self.check_share(post, password).await {
+            return Ok(Some(SharePermission::Edit));
+        }
+
+        self.count_wrong_password(post, &slug_key, ip_key.as_deref())
+            .await?;
+
+        Err(DatabaseError::PasswordIncorrect)
+    }
+
+    /// Check that neither a post nor an IP is locked after too many wrong passwords
+    ///
+    /// # Arguments
+    /// * `slug_key` - the cache key of the post's counter
+    /// * `ip_key` - the cache key of the IP's counter, `None` if the IP is unknown
+    async fn check_password_locks(&self, slug_key: &str, ip_key: Option<&str>) -> Result<()> {
+        if self.is_password_locked(slug_key).await {
+            return Err(DatabaseError::TooManyAttempts);
+        }
+
+        if let Some(ip_key) = ip_key {
+            if self.is_password_locked(ip_key).await {
+                return Err(DatabaseError::TooManyAttempts);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Forget the wrong passwords of a post and IP after the right password is given
+    ///
+    /// # Arguments
+    /// * `slug_key` - the cache key of the post's counter
+    /// * `ip_key` - the cache key of the IP's counter, `None` if the IP is unknown
+    async fn clear_password_failures(&self, slug_key: &str, ip_key: Option<&str>) {
+        for key in [Some(slug_key), ip_key].into_iter().flatten() {
+            self.base.cachedb.remove(key.to_string()).await;
+        }
+    }
+
+    /// Count a wrong password against a post and IP, auditing the post being locked
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `slug_key` - the cache key of the post's counter
+    /// * `ip_key` - the cache key of the IP's counter, `None` if the IP is unknown
+    async fn count_wrong_password(
+        &self,
+        post: &Post,
+        slug_key: &str,
+        ip_key: Option<&str>,
+    ) -> Result<()> {
+        if self.record_password_failure(slug_key).await
+            && self
+                .auth
+                .audit(
+                    "0".to_string(),
+                    format!(
+                        "Locked a post after too many wrong passwords: {}",
+                        post.slug
+                    ),
+                )
+                .await
+                .is_err()
         {
-            "INSERT INTO \":t\" VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+            return Err(DatabaseError::Other);
+        }
+
+        if let Some(ip_key) = ip_key {
+            self.record_password_failure(ip_key).await;
+        }
+
+        Ok(())
+    }
+
+    /// Get the cache key of the wrong password counter of an IP, `None` if the IP is unknown
+    ///
+    /// # Arguments
+    /// * `ip` - the IP address
+    fn ip_failures_key(&self, ip: &str) -> Option<String> {
+        if ip.is_empty() {
+            return None;
+        }
+
+        Some(self.failures_key("ip", &utility::hash(ip.to_string())))
+    }
+
+    /// Get the cache key of the wrong password counter of a post or IP
+    ///
+    /// # Arguments
+    /// * `kind` - `slug`, `view` or `ip`
+    /// * `id` - the slug or hashed IP
+    fn failures_key(&self, kind: &str, id: &str) -> String {
+        format!("{}.failures:{kind}:{id}", self.config.table_posts.prefix)
+    }
+
+    /// Check if the wrong password counter at a cache key is locked
+    ///
+    /// # Arguments
+    /// * `key` - the cache key
+    async fn is_password_locked(&self, key: &str) -> bool {
+        let Some(c) = self.base.cachedb.get(key.to_string()).await else {
+            return false;
+        };
+
+        let now = utility::unix_epoch_timestamp();
+        let failures = PasswordFailures::decode(c.parse().unwrap_or(0));
+
+        if failures.is_stale(now) {
+            // the cache has no expiry, forgotten counters are removed once they're seen
+            self.base.cachedb.remove(key.to_string()).await;
+            return false;
+        }
+
+        failures.locked_until() > now
+    }
+
+    /// Count a wrong password, locking once there are too many
+    ///
+    /// Every wrong password after [`FAILURES_BEFORE_LOCK`] doubles the lock.
+    ///
+    /// # Arguments
+    /// * `key` - the cache key
+    ///
+    /// # Returns
+    /// * `true` if this wrong password caused a lock
+    async fn record_password_failure(&self, key: &str) -> bool {
+        let now = utility::unix_epoch_timestamp();
+        let mut failures = PasswordFailures::decode(self.incr_counter(key).await);
+
+        if failures.is_stale(now) {
+            // forget old wrong passwords (only a counter's first wrong passwords can race here)
+            failures = PasswordFailures {
+                since: now,
+                failures: 1,
+            };
+
+            self.base
+                .cachedb
+                .set(key.to_string(), failures.encode())
+                .await;
+        }
+
+        if failures.failures < u128::from(FAILURES_BEFORE_LOCK) {
+            return false;
+        }
+
+        // the lock starts now
+        failures.since = now;
+        self.base
+            .cachedb
+            .set(key.to_string(), failures.encode())
+            .await;
+
+        true
+    }
+
+    /// Take a token from the rate limit bucket of a profile or IP
+    ///
+    /// # Arguments
+    /// * `group` - the route group being limited
+    /// * `key` - the profile or hashed IP the request is counted under
+    pub async fn take_rate_limit_token(&self, group: RateLimitGroup, key: &str) -> RateLimitStatus {
+        let cache_key = format!(
+            "{}.ratelimit:{}:{key}",
+            self.config.table_posts.prefix,
+            group.name()
+        );
+
+        let limit = group.limit(&self.config);
+        let full_at = self.incr_counter(&cache_key).await;
+
+        let (status, store) =
+            ratelimit::take_token(limit, full_at, utility::unix_epoch_timestamp());
+
+        if let Some(store) = store {
+            // (only a full or empty bucket is overwritten, requests racing on a full one
+            // can share its first token)
+            self.base.cachedb.set(cache_key, store.to_string()).await;
+        }
+
+        status
+    }
+
+    /// Add one to the counter at a cache key, returning the new value
+    ///
+    /// A missing counter counts from `0`. Concurrent calls never lose a count.
+    ///
+    /// # Arguments
+    /// * `key` - the cache key
+    async fn incr_counter(&self, key: &str) -> u128 {
+        // the return value of `incr` differs between cache backends, so the count is read back
+        self.base.cachedb.incr(key.to_string()).await;
+
+        match self.base.cachedb.get(key.to_string()).await {
+            Some(c) => c.parse().unwrap_or(1),
+            None => {
+                // (backends which don't create missing keys)
+                self.base
+                    .cachedb
+                    .set(key.to_string(), "1".to_string())
+                    .await;
+                1
+            }
+        }
+    }
+
+    /// Check the view password of a post in constant time
+    ///
+    /// View passwords stored in plain text or with an old hash are rehashed once they're entered.
+    ///
+    /// # Arguments
+    /// * `slug` - the post
+    /// * `password` - the view password
+    /// * `ip` - the IP address of the user giving the password
+    ///
+    /// # Returns
+    /// * `(post, token)`, the token unlocks the post for [`UNLOCK_DURATION`]
+    /// * `DatabaseError::TooManyAttempts` if the post or IP is locked
+    pub async fn unlock_post_view(
+        &self,
+        slug: String,
+        password: String,
+        ip: &str,
+    ) -> Result<(Post, String)> {
+        let mut post = self.get_post(slug).await?;
+
+        if post.context.view_password.is_empty() {
+            // nothing to unlock
+            return Ok((post.clone(), self.unlock_token(&post)));
+        }
+
+        // view passwords are counted apart from edit passwords, so guessing one
+        // doesn't lock the post's editors out
+        let slug_key = self.failures_key("view", &post.slug);
+        let ip_key = self.ip_failures_key(ip);
+
+        // check locks before hashing anything
+        self.check_password_locks(&slug_key, ip_key.as_deref())
+            .await?;
+
+        let stored = &post.context.view_password;
+
+        let correct = if password::is_hash(stored) {
+            password::verify(&password, stored)
         } else {
-            "INSERT INTO \":t\" VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+            bool::from(password.as_bytes().ct_eq(stored.as_bytes()))
+        };
+
+        if !correct {
+            self.count_wrong_password(&post, &slug_key, ip_key.as_deref())
+                .await?;
+
+            return Err(DatabaseError::PasswordIncorrect);
+        }
+
+        self.clear_password_failures(&slug_key, ip_key.as_deref())
+            .await;
+
+        if password::is_hash(stored) && !password::needs_rehash(stored) {
+            let token = self.unlock_token(&post);
+            return Ok((post, token));
         }
-        .to_string()
-        .replace(":t", &self.config.table_posts.table_name);
+
+        // old (or plain text) view password, hash it now
+        post.context.view_password = password::hash(&password);
+        self.update_post_context(&post.slug, &post.context).await?;
+
+        let token = self.unlock_token(&post);
+        Ok((post, token))
+    }
+
+    /// Sign the view password unlock of a post, changing the view password invalidates it
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `expires_at` - the time the unlock stops working
+    fn sign_unlock(&self, post: &Post, expires_at: u128) -> Hmac<Sha256> {
+        let mut mac = self.mac();
+
+        mac.update(
+            format!(
+                "unlock:{}:{}:{}",
+                post.id, post.context.view_password, expires_at
+            )
+            .as_bytes(),
+        );
+
+        mac
+    }
+
+    /// Get a token unlocking a post's view password for [`UNLOCK_DURATION`]
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    pub fn unlock_token(&self, post: &Post) -> String {
+        let expires_at = utility::unix_epoch_timestamp() + UNLOCK_DURATION;
+
+        format!(
+            "{}.{}",
+            expires_at,
+            hex::encode(self.sign_unlock(post, expires_at).finalize().into_bytes())
+        )
+    }
+
+    /// Check a token from [`Database::unlock_token`]
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `token` - the token
+    pub fn check_unlock(&self, post: &Post, token: &str) -> bool {
+        let Some((expires_at, signature)) = token.split_once('.') else {
+            return false;
+        };
+
+        let (Ok(expires_at), Ok(signature)) = (expires_at.parse::<u128>(), hex::decode(signature))
+        else {
+            return false;
+        };
+
+        if utility::unix_epoch_timestamp() >= expires_at {
+            return false;
+        }
+
+        // compared in constant time
+        self.sign_unlock(post, expires_at)
+            .verify_slice(&signature)
+            .is_ok()
+    }
+
+    /// Get all posts owned by a profile, newest first
+    ///
+    /// # Arguments
+    /// * `owner` - the ID of the profile
+    /// * `page` - the page number
+    ///
+    /// # Returns
+    /// * `(posts, has_next)`, `has_next` counts every row fetched, so it stays right
+    ///   when some of them are left out
+    pub async fn get_posts_by_owner(&self, owner: String, page: u32) -> Result<(Vec<Post>, bool)> {
+        if !self.config.post_ownership {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            format!("SELECT * FROM \":t\" WHERE \":context\" LIKE ? ORDER BY \":date_published\" DESC LIMIT {POSTS_PER_PAGE} OFFSET {}", page_offset(page))
+        } else {
+            format!("SELECT * FROM \":t\" WHERE \":context\" LIKE $1 ORDER BY \":date_published\" DESC LIMIT {POSTS_PER_PAGE} OFFSET {}", page_offset(page))
+        }
+        .replace(":t", &self.config.table_posts.table_name)
+        .replace(":context", &self.config.table_posts.context)
+        .replace(":date_published", &self.config.table_posts.date_published);
 
         let c = &self.base.db.client;
         match sqlquery(&query)
-            .bind::<&String>(&post.id)
-            .bind::<&String>(&post.slug)
-            .bind::<&String>(&post.password)
-            .bind::<&String>(&post.content)
-            .bind::<&String>(&post.date_published.to_string())
-            .bind::<&String>(&post.date_edited.to_string())
-            .bind::<&String>(match serde_json::to_string(&post.context) {
-                Ok(ref s) => s,
-                Err(_) => return Err(DatabaseError::ValueError),
-            })
-            .bind::<&String>(match serde_json::to_string(&post.ips) {
-                Ok(ref s) => s,
-                Err(_) => return Err(DatabaseError::ValueError),
-            })
-            .execute(c)
+            .bind::<&String>(&format!("%\"owner\":\"{owner}\"%"))
+            .fetch_all(c)
             .await
         {
-            Ok(_) => return Ok((props.password, post)),
-            Err(_) => return Err(DatabaseError::Other),
-        };
+            Ok(rows) => {
+                let has_next = rows.len() == POSTS_PER_PAGE as usize;
+                let mut out = Vec::new();
+
+                for row in rows {
+                    let post = self.gimme_post(self.base.textify_row(row).0)?;
+
+                    if post.is_expired() {
+                        continue;
+                    }
+
+                    out.push(post);
+                }
+
+                Ok((out, has_next))
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
     }
 
-    /// Use an existing post as a template
+    /// Get public posts for the explore page
+    ///
+    /// View counts are the ones stored in the database, views which weren't flushed yet
+    /// don't count towards [`PostSort::Views`].
     ///
     /// # Arguments
-    /// * `props` - [`PostClone`]
+    /// * `sort` - how to order the posts
+    /// * `page` - the page number
+    ///
+    /// # Returns
+    /// * `(posts, has_next)`, `has_next` counts every row fetched, so it stays right
+    ///   when some of them are left out
+    pub async fn get_public_posts(&self, sort: PostSort, page: u32) -> Result<(Vec<Post>, bool)> {
+        let query = public_posts_query(&self.base.db.r#type, &self.config, sort, page);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).fetch_all(c).await {
+            Ok(rows) => {
+                let has_next = rows.len() == POSTS_PER_PAGE as usize;
+                let mut out = Vec::new();
+
+                for row in rows {
+                    let post = self.gimme_post(self.base.textify_row(row).0)?;
+
+                    if !post.is_searchable() | post.is_expired() {
+                        continue;
+                    }
+
+                    out.push(post);
+                }
+
+                Ok((out, has_next))
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Get the owner of a post created by `user`
+    fn owner_of_new_post(&self, user: &Option<Box<Profile>>) -> String {
+        match user {
+            Some(ua) if self.config.post_ownership => ua.id.clone(),
+            _ => String::new(),
+        }
+    }
+
+    /// Create a new post
+    ///
+    /// # Arguments
+    /// * `props` - [`PostCreate`]
     /// * `ip` - the IP address where this post was created
+    /// * `user` - the profile creating the post, which owns it
     ///
     /// # Returns
     /// * Result containing a tuple with the unhashed edit password and the post
-    pub async fn clone_post(&self, mut props: ClonePost, ip: String) -> Result<(String, Post)> {
+    pub async fn create_post(
+        &self,
+        mut props: CreatePost,
+        ip: String,
+        user: Option<Box<Profile>>,
+    ) -> Result<(String, Post)> {
+        check_captcha(
+            self.captcha.as_ref(),
+            &self.config,
+            &props.captcha_response,
+            &ip,
+            user.as_deref(),
+        )
+        .await?;
+
         props.slug = idna::punycode::encode_str(&props.slug)
             .unwrap()
             .to_lowercase();
@@ -288,11 +1094,10 @@ impl Database {
             return Err(DatabaseError::AlreadyExists);
         }
 
-        // make sure post source exists
-        let source = match self.get_post(props.source).await {
-            Ok(p) => p,
-            Err(e) => return Err(e),
-        };
+        // old slugs of renamed posts are reserved
+        if self.get_alias(props.slug.clone()).await.is_ok() {
+            return Err(DatabaseError::AlreadyExists);
+        }
 
         // create slug if not supplied
         if props.slug.is_empty() {
@@ -300,7 +1105,10 @@ impl Database {
         }
 
         // create random password if not supplied
-        if props.password.is_empty() {
+        // (owned posts don't need a password, their owner can always edit them)
+        let owner = self.owner_of_new_post(&user);
+
+        if props.password.is_empty() && owner.is_empty() {
             props.password = utility::random_id().chars().take(10).collect();
         }
 
@@ -309,6 +1117,14 @@ impl Database {
             return Err(DatabaseError::ValueError);
         }
 
+        if (props.content.len() > 200_000) | (props.content.len() < 1) {
+            return Err(DatabaseError::ValueError);
+        }
+
+        if (props.expires_at != 0) && (props.expires_at <= utility::unix_epoch_timestamp()) {
+            return Err(DatabaseError::ValueError);
+        }
+
         // (characters used)
         let regex = regex::RegexBuilder::new("^[\\w\\_\\-\\.\\!\\p{Extended_Pictographic}]+$")
             .multi_line(true)
@@ -319,16 +1135,30 @@ impl Database {
             return Err(DatabaseError::ValueError);
         }
 
+        if RESERVED_SLUGS.contains(&props.slug.as_str()) {
+            return Err(DatabaseError::AlreadyExists);
+        }
+
         // ...
-        let source_c = source.clone();
         let post = Post {
             id: utility::random_id(),
             slug: props.slug,
-            content: source.content,
-            password: utility::hash(props.password.clone()),
+            content: props.content,
+            password: if props.password.is_empty() {
+                // no password can match this
+                String::new()
+            } else {
+                password::hash(&props.password)
+            },
             date_published: utility::unix_epoch_timestamp(),
             date_edited: utility::unix_epoch_timestamp(),
-            context: PostContext::from(source_c), // use other post as a template
+            context: PostContext {
+                owner,
+                expires_at: props.expires_at,
+                burn_after_read: props.burn_after_read,
+                visibility: props.visibility,
+                ..Default::default()
+            },
             ips: vec![(utility::unix_epoch_timestamp(), ip)],
         };
 
@@ -361,20 +1191,177 @@ impl Database {
             .execute(c)
             .await
         {
-            Ok(_) => return Ok((props.password, post)),
+            Ok(_) => {
+                self.index_post(&post).await?;
+                return Ok((props.password, post));
+            }
             Err(_) => return Err(DatabaseError::Other),
         };
     }
 
-    /// Delete an existing post
+    /// Use an existing post as a template
     ///
     /// # Arguments
-    /// * `slug` - the post to delete
-    /// * `password` - the post's edit password
-    pub async fn delete_post(
+    /// * `props` - [`PostClone`]
+    /// * `ip` - the IP address where this post was created
+    /// * `user` - the profile creating the post, which owns it
+    ///
+    /// # Returns
+    /// * Result containing a tuple with the unhashed edit password and the post
+    pub async fn clone_post(
         &self,
-        mut slug: String,
-        password: String,
+        mut props: ClonePost,
+        ip: String,
+        user: Option<Box<Profile>>,
+    ) -> Result<(String, Post)> {
+        check_captcha(
+            self.captcha.as_ref(),
+            &self.config,
+            &props.captcha_response,
+            &ip,
+            user.as_deref(),
+        )
+        .await?;
+
+        props.slug = idna::punycode::encode_str(&props.slug)
+            .unwrap()
+            .to_lowercase();
+
+        if props.slug.ends_with("-") {
+            props.slug.pop();
+        }
+
+        // make sure post doesn't already exist
+        if let Ok(_) = self.get_post(props.slug.clone()).await {
+            return Err(DatabaseError::AlreadyExists);
+        }
+
+        // old slugs of renamed posts are reserved
+        if self.get_alias(props.slug.clone()).await.is_ok() {
+            return Err(DatabaseError::AlreadyExists);
+        }
+
+        // make sure post source exists
+        let source = match self.get_post(props.source).await {
+            Ok(p) => p,
+            Err(e) => return Err(e),
+        };
+
+        // the clone copies the content, so the source must be viewable by this user
+        // (view password included, the clone itself has none)
+        let unlock = (!props.unlock.is_empty()).then_some(props.unlock.as_str());
+        self.can_view(&source, user.as_deref(), &props.share, unlock)
+            .await?;
+
+        // cloning would keep a copy of a post meant to be read once
+        if source.context.burn_after_read {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // create slug if not supplied
+        if props.slug.is_empty() {
+            props.slug = utility::random_id().chars().take(10).collect();
+        }
+
+        // create random password if not supplied
+        // (owned posts don't need a password, their owner can always edit them)
+        let owner = self.owner_of_new_post(&user);
+
+        if props.password.is_empty() && owner.is_empty() {
+            props.password = utility::random_id().chars().take(10).collect();
+        }
+
+        // check lengths
+        if (props.slug.len() > 250) | (props.slug.len() < 3) {
+            return Err(DatabaseError::ValueError);
+        }
+
+        // (characters used)
+        let regex = regex::RegexBuilder::new("^[\\w\\_\\-\\.\\!\\p{Extended_Pictographic}]+$")
+            .multi_line(true)
+            .build()
+            .unwrap();
+
+        if regex.captures(&props.slug).iter().len() < 1 {
+            return Err(DatabaseError::ValueError);
+        }
+
+        if RESERVED_SLUGS.contains(&props.slug.as_str()) {
+            return Err(DatabaseError::AlreadyExists);
+        }
+
+        // ...
+        let source_c = source.clone();
+        let post = Post {
+            id: utility::random_id(),
+            slug: props.slug,
+            content: source.content,
+            password: if props.password.is_empty() {
+                // no password can match this
+                String::new()
+            } else {
+                password::hash(&props.password)
+            },
+            date_published: utility::unix_epoch_timestamp(),
+            date_edited: utility::unix_epoch_timestamp(),
+            context: PostContext {
+                owner,
+                ..PostContext::from(source_c) // use other post as a template
+            },
+            ips: vec![(utility::unix_epoch_timestamp(), ip)],
+        };
+
+        // create post
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \":t\" VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \":t\" VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_posts.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&String>(&post.id)
+            .bind::<&String>(&post.slug)
+            .bind::<&String>(&post.password)
+            .bind::<&String>(&post.content)
+            .bind::<&String>(&post.date_published.to_string())
+            .bind::<&String>(&post.date_edited.to_string())
+            .bind::<&String>(match serde_json::to_string(&post.context) {
+                Ok(ref s) => s,
+                Err(_) => return Err(DatabaseError::ValueError),
+            })
+            .bind::<&String>(match serde_json::to_string(&post.ips) {
+                Ok(ref s) => s,
+                Err(_) => return Err(DatabaseError::ValueError),
+            })
+            .execute(c)
+            .await
+        {
+            Ok(_) => {
+                self.index_post(&post).await?;
+                return Ok((props.password, post));
+            }
+            Err(_) => return Err(DatabaseError::Other),
+        };
+    }
+
+    /// Delete an existing post
+    ///
+    /// Managers can delete any post, taking a post down (see [`Self::take_down_post`]) is done
+    /// through [`Self::moderate_post`].
+    ///
+    /// # Arguments
+    /// * `slug` - the post to delete
+    /// * `ip` - the IP address of the user deleting this post
+    /// * `password` - the post's edit password
+    pub async fn delete_post(
+        &self,
+        mut slug: String,
+        ip: String,
+        password: String,
         user: Option<Box<Profile>>,
     ) -> Result<()> {
         slug = idna::punycode::encode_str(&slug).unwrap().to_lowercase();
@@ -389,6 +1376,11 @@ impl Database {
             Err(err) => return Err(err),
         };
 
+        // taken down posts keep their slug reserved
+        if existing.is_taken_down() {
+            return Err(DatabaseError::NotAllowed);
+        }
+
         // check password
         if let Some(ref ua) = user {
             // check permission
@@ -397,11 +1389,12 @@ impl Database {
                 Err(_) => return Err(DatabaseError::Other),
             };
 
-            if ua.id != existing.context.owner {
+            if !self.has_post_role(&existing, ua, AclRole::Admin) {
                 if !group.permissions.check_manager() {
                     // check password, not paste owner
-                    if utility::hash(password) != existing.password {
-                        return Err(DatabaseError::PasswordIncorrect);
+                    if self.unlock_post(&existing, &password, &ip).await?.is_some() {
+                        // share links can't delete posts
+                        return Err(DatabaseError::NotAllowed);
                     }
                 } else {
                     if let Err(_) = self
@@ -418,37 +1411,106 @@ impl Database {
             }
         } else {
             // check password, no account
-            if utility::hash(password) != existing.password {
-                return Err(DatabaseError::PasswordIncorrect);
+            if self.unlock_post(&existing, &password, &ip).await?.is_some() {
+                // share links can't delete posts
+                return Err(DatabaseError::NotAllowed);
             }
         }
 
-        // delete post view count
-        self.base
-            .cachedb
-            .remove(format!("{}:{}", self.config.table_views.prefix, slug))
-            .await;
+        // ...
+        self.remove_post(&existing).await
+    }
 
+    /// Remove an existing post along with its views and revisions, without checking
+    /// any permissions
+    ///
+    /// # Arguments
+    /// * `post` - the post to remove
+    ///
+    /// # Returns
+    /// * `DatabaseError::NotFound` if the post was already removed by another request
+    pub async fn remove_post(&self, post: &Post) -> Result<()> {
         // delete post
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
-            "DELETE FROM \":t\" WHERE \":slug\" = ?"
+            "DELETE FROM \":t\" WHERE \":slug\" = ? AND \":id\" = ?"
         } else {
-            "DELETE FROM \":t\" WHERE \":slug\" = $1"
+            "DELETE FROM \":t\" WHERE \":slug\" = $1 AND \":id\" = $2"
         }
         .to_string()
         .replace(":t", &self.config.table_posts.table_name)
-        .replace(":slug", &self.config.table_posts.slug);
+        .replace(":slug", &self.config.table_posts.slug)
+        .replace(":id", &self.config.table_posts.id);
 
         let c = &self.base.db.client;
-        match sqlquery(&query).bind::<&String>(&slug).execute(c).await {
-            Ok(_) => {
+        match sqlquery(&query)
+            .bind::<&String>(&post.slug)
+            .bind::<&String>(&post.id)
+            .execute(c)
+            .await
+        {
+            Ok(r) => {
+                if r.rows_affected() == 0 {
+                    // somebody else got here first
+                    return Err(DatabaseError::NotFound);
+                }
+
                 // remove from cache
                 self.base
                     .cachedb
-                    .remove(format!("{}:{}", self.config.table_posts.prefix, slug))
+                    .remove(format!("{}:{}", self.config.table_posts.prefix, post.slug))
+                    .await;
+
+                // delete post view count
+                self.base
+                    .cachedb
+                    .remove(format!("{}:{}", self.config.table_views.prefix, post.slug))
                     .await;
 
+                // delete revisions
+                self.delete_revisions_by_post(post.id.clone()).await?;
+
+                // delete aliases
+                self.delete_aliases_by_target(post.slug.clone()).await?;
+
+                // delete share links
+                self.delete_shares_by_post(post.id.clone()).await?;
+
+                // delete reports
+                self.delete_reports_by_post(post.id.clone()).await?;
+
+                // remove from search
+                self.unindex_post(post.id.clone()).await?;
+                self.delete_tags_by_post(post.id.clone()).await?;
+
+                // remove from collections
+                self.remove_post_from_collections(post.slug.clone()).await?;
+
+                if self.config.view_mode == ViewMode::OpenMultiple {
+                    // delete stored view count
+                    self.pending_views.lock().unwrap().remove(&post.slug);
+
+                    let query: String =
+                        if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+                            "DELETE FROM \":t\" WHERE \"slug\" = ?"
+                        } else {
+                            "DELETE FROM \":t\" WHERE \"slug\" = $1"
+                        }
+                        .replace(":t", &self.config.table_view_counts.table_name);
+
+                    if sqlquery(&query)
+                        .bind::<&String>(&post.slug)
+                        .execute(c)
+                        .await
+                        .is_err()
+                    {
+                        return Err(DatabaseError::Other);
+                    };
+                }
+
+                // delete daily stats
+                self.delete_stats_by_slug(post.slug.clone()).await?;
+
                 if self.config.view_mode == ViewMode::AuthenticatedOnce {
                     // delete all view logs
                     let query: String =
@@ -459,20 +1521,26 @@ impl Database {
                         }
                         .replace(":t", &self.config.table_views.table_name);
 
-                    if let Err(_) = sqlquery(&query).bind::<&String>(&slug).execute(c).await {
+                    if sqlquery(&query)
+                        .bind::<&String>(&post.slug)
+                        .execute(c)
+                        .await
+                        .is_err()
+                    {
                         return Err(DatabaseError::Other);
                     };
                 }
 
-                // return
-                return Ok(());
+                Ok(())
             }
-            Err(_) => return Err(DatabaseError::Other),
-        };
+            Err(_) => Err(DatabaseError::Other),
+        }
     }
 
     /// Edit an existing post
     ///
+    /// ACL editors can edit the content, but not the slug or password.
+    ///
     /// # Arguments
     /// * `slug` - the post to edit
     /// * `ip` - the IP address of the user editing this post
@@ -480,6 +1548,8 @@ impl Database {
     /// * `new_content` - the new content of the post
     /// * `new_slug` - the new slug of the post
     /// * `new_password` - the new password of the post
+    /// * `rewrite_links` - if the `next`/`previous` links of the post's neighbours should
+    ///   be updated when the slug changes
     pub async fn edit_post(
         &self,
         mut slug: String,
@@ -488,6 +1558,7 @@ impl Database {
         new_content: String,
         mut new_slug: String,
         mut new_password: String,
+        rewrite_links: bool,
         user: Option<Box<Profile>>,
     ) -> Result<()> {
         slug = idna::punycode::encode_str(&slug).unwrap().to_lowercase();
@@ -502,7 +1573,15 @@ impl Database {
             Err(err) => return Err(err),
         };
 
+        if existing.is_taken_down() {
+            return Err(DatabaseError::NotAllowed);
+        }
+
         // check password
+        let mut shared = None;
+        let mut role = None;
+        let mut manager = false;
+
         if let Some(ref ua) = user {
             // check permission
             let group = match self.auth.get_group_by_id(ua.group).await {
@@ -510,12 +1589,12 @@ impl Database {
                 Err(_) => return Err(DatabaseError::Other),
             };
 
-            if ua.id != existing.context.owner {
-                if !group.permissions.check_manager() {
-                    // check password, not paste owner
-                    if utility::hash(password) != existing.password {
-                        return Err(DatabaseError::PasswordIncorrect);
-                    }
+            manager = group.permissions.check_manager();
+
+            if !self.has_post_role(&existing, ua, AclRole::Editor) {
+                if !manager {
+                    // check password (or share link), not paste owner
+                    shared = self.unlock_post(&existing, &password, &ip).await?;
                 } else {
                     if let Err(_) = self
                         .auth
@@ -528,32 +1607,68 @@ impl Database {
                         return Err(DatabaseError::Other);
                     }
                 }
+            } else {
+                role = self.get_post_role(&existing, ua);
             }
         } else {
-            // check password, no account
-            if utility::hash(password) != existing.password {
-                return Err(DatabaseError::PasswordIncorrect);
-            }
+            // check password (or share link), no account
+            shared = self.unlock_post(&existing, &password, &ip).await?;
+        }
+
+        // share links and ACL editors can't change the slug or password
+        if (!new_password.is_empty() | (!new_slug.is_empty() && (new_slug != slug)))
+            && !can_change_slug_or_password(role, shared.is_some(), manager)
+        {
+            return Err(DatabaseError::NotAllowed);
         }
 
         // hash new password
         if !new_password.is_empty() {
-            new_password = utility::hash(new_password);
+            new_password = password::hash(&new_password);
+        } else if password::needs_rehash(&existing.password)
+            && password::verify(&password, &existing.password)
+        {
+            // upgrade the old hash now that we know the password
+            new_password = password::hash(&password);
         } else {
-            new_password = existing.password;
+            new_password = existing.password.clone();
         }
 
         // update new_slug
         if new_slug.is_empty() {
-            new_slug = existing.slug;
+            new_slug = existing.slug.clone();
         }
 
-        new_slug = idna::punycode::encode_str(&new_slug).unwrap();
+        new_slug = idna::punycode::encode_str(&new_slug)
+            .unwrap()
+            .to_lowercase();
 
         if new_slug.ends_with("-") {
             new_slug.pop();
         }
 
+        if new_slug != slug {
+            // make sure the new slug isn't taken
+            if RESERVED_SLUGS.contains(&new_slug.as_str()) {
+                return Err(DatabaseError::AlreadyExists);
+            }
+
+            if self.get_post(new_slug.clone()).await.is_ok() {
+                return Err(DatabaseError::AlreadyExists);
+            }
+
+            // (renaming a post back to one of its old slugs is fine)
+            if let Ok(target) = self.get_alias(new_slug.clone()).await {
+                if target != slug {
+                    return Err(DatabaseError::AlreadyExists);
+                }
+            }
+        }
+
+        // store the current version of the post
+        self.create_revision(&existing, self.revision_editor(&user, &ip))
+            .await?;
+
         // push ip
         existing.ips.push((utility::unix_epoch_timestamp(), ip));
 
@@ -566,7 +1681,7 @@ impl Database {
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
             "UPDATE \":t\" SET \":content\" = ?, \":password\" = ?, \":slug\" = ?, \":date_edited\" = ?, \":ips\" = ? WHERE \":slug\" = ?"
         } else {
-            "UPDATE \":t\" SET (\":content\" = $1, \":password\" = $2, \":slug\" = $3, \":date_edited\" = $4, \":ips\" = $5) WHERE \":slug\" = $6"
+            "UPDATE \":t\" SET \":content\" = $1, \":password\" = $2, \":slug\" = $3, \":date_edited\" = $4, \":ips\" = $5 WHERE \":slug\" = $6"
         }
         .to_string()
         .replace(":t", &self.config.table_posts.table_name)
@@ -597,24 +1712,80 @@ impl Database {
                     .remove(format!("{}:{}", self.config.table_posts.prefix, slug))
                     .await;
 
+                if new_slug != slug {
+                    // keep old links working
+                    self.create_alias(slug.clone(), new_slug.clone()).await?;
+                    self.move_views(slug.clone(), new_slug.clone()).await;
+
+                    if rewrite_links {
+                        self.rewrite_neighbour_links(&existing.context, &slug, &new_slug)
+                            .await?;
+                    }
+
+                    // collections follow the post to its new slug
+                    self.rename_collection_post(slug.clone(), new_slug.clone())
+                        .await?;
+                }
+
+                self.sync_search_index(&new_slug).await?;
+
                 // return
-                return Ok(());
+                Ok(())
             }
-            Err(_) => return Err(DatabaseError::Other),
-        };
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Point the `next`/`previous` links of a post's neighbours at its new slug
+    ///
+    /// # Arguments
+    /// * `context` - the context of the renamed post
+    /// * `old_slug` - the slug the post used to have
+    /// * `new_slug` - the slug the post has now
+    async fn rewrite_neighbour_links(
+        &self,
+        context: &PostContext,
+        old_slug: &String,
+        new_slug: &String,
+    ) -> Result<()> {
+        if !context.next.is_empty() {
+            if let Ok(mut next) = self.get_post(context.next.clone()).await {
+                if &next.context.previous == old_slug {
+                    next.context.previous = new_slug.to_owned();
+                    self.update_post_context(&next.slug, &next.context).await?;
+                }
+            }
+        }
+
+        if !context.previous.is_empty() {
+            if let Ok(mut previous) = self.get_post(context.previous.clone()).await {
+                if &previous.context.next == old_slug {
+                    previous.context.next = new_slug.to_owned();
+                    self.update_post_context(&previous.slug, &previous.context)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(())
     }
 
     /// Edit an existing post's context by `slug`
     ///
     /// # Arguments
     /// * `slug` - the post to edit
+    /// * `ip` - the IP address of the user editing this post
     /// * `password` - the post's edit password
     /// * `context` - the new context of the post
+    /// * `new_view_password` - the new view password of the post, `None` to keep it and
+    ///   an empty string to remove it
     pub async fn edit_post_context(
         &self,
         mut slug: String,
+        ip: String,
         password: String,
         mut context: PostContext,
+        new_view_password: Option<String>,
         user: Option<Box<Profile>>,
     ) -> Result<()> {
         slug = idna::punycode::encode_str(&slug).unwrap().to_lowercase();
@@ -629,7 +1800,13 @@ impl Database {
             Err(err) => return Err(err),
         };
 
+        if existing.is_taken_down() {
+            return Err(DatabaseError::NotAllowed);
+        }
+
         // check password
+        let mut shared = None;
+
         if let Some(ref ua) = user {
             // check permission
             let group = match self.auth.get_group_by_id(ua.group).await {
@@ -637,12 +1814,10 @@ impl Database {
                 Err(_) => return Err(DatabaseError::Other),
             };
 
-            if ua.id != existing.context.owner {
+            if !self.has_post_role(&existing, ua, AclRole::Editor) {
                 if !group.permissions.check_manager() {
-                    // check password, not paste owner
-                    if utility::hash(password) != existing.password {
-                        return Err(DatabaseError::PasswordIncorrect);
-                    }
+                    // check password (or share link), not paste owner
+                    shared = self.unlock_post(&existing, &password, &ip).await?;
                 } else {
                     if let Err(_) = self
                         .auth
@@ -656,38 +1831,200 @@ impl Database {
                     }
                 }
             }
-
-            // update owner
-            context.owner = ua.id.clone();
         } else {
-            // check password, no account
-            if utility::hash(password) != existing.password {
-                return Err(DatabaseError::PasswordIncorrect);
+            // check password (or share link), no account
+            shared = self.unlock_post(&existing, &password, &ip).await?;
+        }
+
+        // ownership only changes through `transfer_post`, access through `set_post_role`
+        context.owner = existing.context.owner.clone();
+        context.acl = existing.context.acl.clone();
+        // moderation only changes through `moderate_post`
+        context.moderation = existing.context.moderation.clone();
+        context.tags = tags::normalize_tags(context.tags)?;
+
+        if shared.is_some() && (context.visibility != existing.context.visibility) {
+            // share links can't change who can view the post
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // the view password is only ever given to us in plain text through `new_view_password`
+        context.view_password = match new_view_password {
+            Some(_) if shared.is_some() => {
+                // share links can't change the view password
+                return Err(DatabaseError::NotAllowed);
             }
+            Some(p) if p.is_empty() => String::new(),
+            Some(p) => password::hash(&p),
+            None => existing.context.view_password.clone(),
+        };
 
-            // clear owner
-            context.owner = String::new();
+        if password::needs_rehash(&existing.password)
+            && password::verify(&password, &existing.password)
+        {
+            // upgrade the old hash now that we know the password
+            self.update_post_password(&slug, &password::hash(&password))
+                .await?;
         }
 
+        // store the current version of the post
+        self.create_revision(&existing, self.revision_editor(&user, &ip))
+            .await?;
+
         // edit post
-        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
-        {
-            "UPDATE \":t\" SET \":metadata\" = ? WHERE \":slug\" = ?"
-        } else {
-            "UPDATE \":t\" SET (\":metadata\" = $1) WHERE \":slug\" = $2"
+        self.update_post_context(&slug, &context).await?;
+        self.set_post_tags(existing.id, &context.tags).await?;
+        self.sync_search_index(&slug).await
+    }
+
+    /// Give an existing post to another profile
+    ///
+    /// # Arguments
+    /// * `slug` - the post to transfer
+    /// * `new_owner` - the username or ID of the profile receiving the post
+    /// * `user` - the profile transferring the post
+    pub async fn transfer_post(
+        &self,
+        mut slug: String,
+        new_owner: String,
+        user: Box<Profile>,
+    ) -> Result<()> {
+        slug = idna::punycode::encode_str(&slug).unwrap().to_lowercase();
+
+        if slug.ends_with("-") {
+            slug.pop();
         }
-        .to_string()
-        .replace(":t", &self.config.table_posts.table_name)
-        .replace(":slug", &self.config.table_posts.slug)
-        .replace(":metadata", &self.config.table_posts.context);
+
+        if !self.config.post_ownership {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // get post
+        let mut existing = self.get_post(slug.clone()).await?;
+
+        // check permission
+        if !self.is_post_owner(&existing, &user) {
+            let group = match self.auth.get_group_by_id(user.group).await {
+                Ok(g) => g,
+                Err(_) => return Err(DatabaseError::Other),
+            };
+
+            if !group.permissions.check_manager() {
+                return Err(DatabaseError::NotAllowed);
+            }
+
+            if self
+                .auth
+                .audit(
+                    user.id.to_owned(),
+                    format!("Transferred a post: {}", existing.slug),
+                )
+                .await
+                .is_err()
+            {
+                return Err(DatabaseError::Other);
+            }
+        }
+
+        // get new owner
+        let new_owner = match self.auth.get_profile(new_owner).await {
+            Ok(ua) => ua,
+            Err(_) => return Err(DatabaseError::NotFound),
+        };
+
+        // update owner
+        existing.context.owner = new_owner.id;
+        self.update_post_context(&existing.slug, &existing.context)
+            .await
+    }
+
+    /// Give a profile a role on an existing post, or take it away
+    ///
+    /// # Arguments
+    /// * `slug` - the post
+    /// * `profile` - the username or ID of the profile
+    /// * `role` - the new role of the profile, `None` to remove it
+    /// * `user` - the profile changing the access control list
+    pub async fn set_post_role(
+        &self,
+        mut slug: String,
+        profile: String,
+        role: Option<AclRole>,
+        user: Box<Profile>,
+    ) -> Result<()> {
+        slug = idna::punycode::encode_str(&slug).unwrap().to_lowercase();
+
+        if slug.ends_with("-") {
+            slug.pop();
+        }
+
+        // get post
+        let mut existing = self.get_post(slug.clone()).await?;
+
+        // check permission
+        if !self.has_post_role(&existing, &user, AclRole::Admin) {
+            let group = match self.auth.get_group_by_id(user.group).await {
+                Ok(g) => g,
+                Err(_) => return Err(DatabaseError::Other),
+            };
+
+            if !group.permissions.check_manager() {
+                return Err(DatabaseError::NotAllowed);
+            }
+
+            if self
+                .auth
+                .audit(
+                    user.id.to_owned(),
+                    format!("Changed a post's access: {}", existing.slug),
+                )
+                .await
+                .is_err()
+            {
+                return Err(DatabaseError::Other);
+            }
+        }
+
+        // get profile
+        let profile = match self.auth.get_profile(profile).await {
+            Ok(ua) => ua,
+            Err(_) => return Err(DatabaseError::NotFound),
+        };
+
+        // update acl
+        match role {
+            Some(role) => existing.context.acl.insert(profile.id, role),
+            None => existing.context.acl.remove(&profile.id),
+        };
+
+        self.update_post_context(&existing.slug, &existing.context)
+            .await
+    }
+
+    /// Replace an existing post's context without checking any permissions
+    ///
+    /// # Arguments
+    /// * `slug` - the post to update
+    /// * `context` - the new context of the post
+    pub async fn update_post_context(&self, slug: &String, context: &PostContext) -> Result<()> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "UPDATE \":t\" SET \":metadata\" = ? WHERE \":slug\" = ?"
+        } else {
+            "UPDATE \":t\" SET \":metadata\" = $1 WHERE \":slug\" = $2"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_posts.table_name)
+        .replace(":slug", &self.config.table_posts.slug)
+        .replace(":metadata", &self.config.table_posts.context);
 
         let c = &self.base.db.client;
         match sqlquery(&query)
-            .bind::<&String>(match serde_json::to_string(&context) {
+            .bind::<&String>(match serde_json::to_string(context) {
                 Ok(ref m) => m,
                 Err(_) => return Err(DatabaseError::ValueError),
             })
-            .bind::<&String>(&slug)
+            .bind::<&String>(slug)
             .execute(c)
             .await
         {
@@ -699,10 +2036,48 @@ impl Database {
                     .await;
 
                 // return
-                return Ok(());
+                Ok(())
             }
-            Err(_) => return Err(DatabaseError::Other),
-        };
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Replace an existing post's edit password hash without checking any permissions
+    ///
+    /// # Arguments
+    /// * `slug` - the post to update
+    /// * `password` - the new hash, from [`password::hash`]
+    pub async fn update_post_password(&self, slug: &String, password: &String) -> Result<()> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "UPDATE \":t\" SET \":password\" = ? WHERE \":slug\" = ?"
+        } else {
+            "UPDATE \":t\" SET \":password\" = $1 WHERE \":slug\" = $2"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_posts.table_name)
+        .replace(":slug", &self.config.table_posts.slug)
+        .replace(":password", &self.config.table_posts.password);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&String>(password)
+            .bind::<&String>(slug)
+            .execute(c)
+            .await
+        {
+            Ok(_) => {
+                // remove from cache
+                self.base
+                    .cachedb
+                    .remove(format!("{}:{}", self.config.table_posts.prefix, slug))
+                    .await;
+
+                // return
+                Ok(())
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
     }
 
     // views
@@ -729,14 +2104,10 @@ impl Database {
             None => {
                 // try to count from "views"
                 if self.config.view_mode == ViewMode::AuthenticatedOnce {
-                    let query: String =
-                        if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-                            "SELECT * FROM \":t\" WHERE \"slug\" = ?"
-                        } else {
-                            "SELECT * FROM \":t\" WHERE \"slug\" = $1"
-                        }
-                        .to_string()
-                        .replace(":t", &self.config.table_views.table_name);
+                    let query = views_by_slug_query(
+                        &self.base.db.r#type,
+                        &self.config.table_views.table_name,
+                    );
 
                     let c = &self.base.db.client;
                     match sqlquery(&query).bind::<&String>(&slug).fetch_all(c).await {
@@ -759,12 +2130,59 @@ impl Database {
                     };
                 }
 
-                // return 0 by default
-                0
+                // rehydrate from "view_counts", including views we haven't flushed yet
+                let views = self.get_stored_views_by_slug(&slug).await
+                    + self
+                        .pending_views
+                        .lock()
+                        .unwrap()
+                        .get(&slug)
+                        .copied()
+                        .unwrap_or(0);
+
+                // store in cache
+                self.base
+                    .cachedb
+                    .set(
+                        format!("{}:{}", self.config.table_views.prefix, slug),
+                        views.to_string(),
+                    )
+                    .await;
+
+                // return
+                views
             }
         }
     }
 
+    /// Get the view count of a post stored in the database
+    ///
+    /// # Arguments
+    /// * `slug` - the slug of the post
+    async fn get_stored_views_by_slug(&self, slug: &String) -> i32 {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \":t\" WHERE \"slug\" = ?"
+        } else {
+            "SELECT * FROM \":t\" WHERE \"slug\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_view_counts.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(slug).fetch_one(c).await {
+            Ok(r) => self
+                .base
+                .textify_row(r)
+                .0
+                .get("count")
+                .unwrap()
+                .parse::<i32>()
+                .unwrap_or(0),
+            Err(_) => 0,
+        }
+    }
+
     /// Update an existing post's view count
     ///
     /// # Arguments
@@ -776,18 +2194,129 @@ impl Database {
             slug.pop();
         }
 
+        // make sure the count is in the cache so we don't increment from nothing
+        self.get_views_by_slug(slug.clone()).await;
+
         // add view
-        // views never reach the database, they're only stored in memory
-        match self
-            .base
+        // (the return value differs between cache backends, so it's not useful here)
+        self.base
             .cachedb
             .incr(format!("{}:{}", self.config.table_views.prefix, slug))
-            .await
+            .await;
+
+        // views are written to the database later by `flush_views`
+        if self.config.view_mode == ViewMode::OpenMultiple {
+            *self.pending_views.lock().unwrap().entry(slug).or_insert(0) += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Write all pending view counts to the database, returns the number of posts updated
+    pub async fn flush_views(&self) -> usize {
+        let pending: Vec<(String, i32)> = self.pending_views.lock().unwrap().drain().collect();
+        let mut flushed: usize = 0;
+
+        let c = &self.base.db.client;
+        for (slug, count) in pending {
+            // update existing count, adding in the database so concurrent flushes can't lose views
+            let query = increment_view_count_query(
+                &self.base.db.r#type,
+                &self.config.table_view_counts.table_name,
+            );
+
+            let res = match sqlquery(&query)
+                .bind::<&String>(&count.to_string())
+                .bind::<&String>(&slug)
+                .execute(c)
+                .await
+            {
+                Ok(r) if r.rows_affected() > 0 => Ok(r),
+                _ => {
+                    // create count
+                    let query: String =
+                        if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+                            "INSERT INTO \":t\" VALUES (?, ?)"
+                        } else {
+                            "INSERT INTO \":t\" VALUES ($1, $2)"
+                        }
+                        .to_string()
+                        .replace(":t", &self.config.table_view_counts.table_name);
+
+                    sqlquery(&query)
+                        .bind::<&String>(&slug)
+                        .bind::<&String>(&count.to_string())
+                        .execute(c)
+                        .await
+                }
+            };
+
+            match res {
+                Ok(_) => flushed += 1,
+                Err(_) => {
+                    // try again next time
+                    *self.pending_views.lock().unwrap().entry(slug).or_insert(0) += count;
+                }
+            }
+        }
+
+        flushed
+    }
+
+    /// Move the view count of a renamed post to its new slug
+    ///
+    /// # Arguments
+    /// * `slug` - the old slug of the post
+    /// * `new_slug` - the new slug of the post
+    pub async fn move_views(&self, slug: String, new_slug: String) {
+        let views = self.get_views_by_slug(slug.clone()).await;
+
+        self.base
+            .cachedb
+            .remove(format!("{}:{}", self.config.table_views.prefix, slug))
+            .await;
+
+        self.base
+            .cachedb
+            .set(
+                format!("{}:{}", self.config.table_views.prefix, new_slug),
+                views.to_string(),
+            )
+            .await;
+
+        // move stored views
+        let table = match self.config.view_mode {
+            ViewMode::AuthenticatedOnce => &self.config.table_views.table_name,
+            ViewMode::OpenMultiple => {
+                let pending = self.pending_views.lock().unwrap().remove(&slug);
+                if let Some(count) = pending {
+                    self.pending_views
+                        .lock()
+                        .unwrap()
+                        .insert(new_slug.clone(), count);
+                }
+
+                &self.config.table_view_counts.table_name
+            }
+        };
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
-            // swapped for some reason??
-            false => Ok(()),
-            true => Err(DatabaseError::Other),
+            "UPDATE \":t\" SET \"slug\" = ? WHERE \"slug\" = ?"
+        } else {
+            "UPDATE \":t\" SET \"slug\" = $1 WHERE \"slug\" = $2"
         }
+        .to_string()
+        .replace(":t", table);
+
+        let c = &self.base.db.client;
+        let _ = sqlquery(&query)
+            .bind::<&String>(&new_slug)
+            .bind::<&String>(&slug)
+            .execute(c)
+            .await;
+
+        self.move_stats(slug, new_slug).await;
     }
 
     /// Check if a user has viewed a post
@@ -797,27 +2326,2614 @@ impl Database {
     /// * `id` - the id of the user
     pub async fn user_has_viewed_post(&self, slug: String, id: String) -> bool {
         if self.config.view_mode == ViewMode::AuthenticatedOnce {
-            let query: String =
-                if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-                    "SELECT * FROM \":t\" WHERE \"slug\" = ? AND \"id\" = ?"
-                } else {
-                    "SELECT * FROM \":t\" WHERE \"slug\" = $1 AND \"id\" = ?"
-                }
-                .to_string()
-                .replace(":t", &self.config.table_views.table_name);
+            let query =
+                view_by_user_query(&self.base.db.r#type, &self.config.table_views.table_name);
 
             let c = &self.base.db.client;
-            match sqlquery(&query)
+            return sqlquery(&query)
                 .bind::<&String>(&slug)
                 .bind::<&String>(&id)
                 .fetch_one(c)
                 .await
-            {
-                Ok(_) => return true,
-                Err(_) => return false,
-            };
+                .is_ok();
         }
 
         false
     }
+
+    /// Count a user's view of a post, only the first view of each user is counted
+    ///
+    /// # Arguments
+    /// * `slug` - the post slug
+    /// * `id` - the id of the user
+    pub async fn create_view_by_user(&self, mut slug: String, id: String) -> Result<()> {
+        if self.config.view_mode != ViewMode::AuthenticatedOnce {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        slug = idna::punycode::encode_str(&slug).unwrap().to_lowercase();
+
+        if slug.ends_with("-") {
+            slug.pop();
+        }
+
+        if self.user_has_viewed_post(slug.clone(), id.clone()).await {
+            return Ok(());
+        }
+
+        // make sure the count is in the cache so we don't increment from nothing
+        self.get_views_by_slug(slug.clone()).await;
+
+        // log view
+        let query = create_view_query(&self.base.db.r#type, &self.config.table_views.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&String>(&slug)
+            .bind::<&String>(&id)
+            .execute(c)
+            .await
+        {
+            Ok(_) => (),
+            // another request logged the view first
+            Err(e)
+                if e.as_database_error()
+                    .is_some_and(|e| e.is_unique_violation()) =>
+            {
+                return Err(DatabaseError::MustBeUnique)
+            }
+            Err(_) => return Err(DatabaseError::Other),
+        }
+
+        // add view
+        self.base
+            .cachedb
+            .incr(format!("{}:{}", self.config.table_views.prefix, slug))
+            .await;
+
+        Ok(())
+    }
+
+    // stats
+
+    /// Count a view in the daily stats of a post
+    ///
+    /// # Arguments
+    /// * `slug` - the slug of the post
+    /// * `ip` - the IP of the viewer, only stored as part of a hash
+    pub fn create_stats_view(&self, slug: String, ip: String) {
+        let day = utility::unix_epoch_timestamp() / DAY;
+
+        let mut pending = self.pending_stats.lock().unwrap();
+        let (views, visitors) = pending.entry((slug, day)).or_default();
+
+        *views += 1;
+        visitors.insert(&utility::hash(ip));
+    }
+
+    /// Get the stored daily stats of a post, `(day, views, visitors)`
+    ///
+    /// # Arguments
+    /// * `slug` - the slug of the post
+    async fn get_stored_stats_by_slug(&self, slug: &String) -> Vec<(u128, i32, HyperLogLog)> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \":t\" WHERE \"slug\" = ?"
+        } else {
+            "SELECT * FROM \":t\" WHERE \"slug\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_stats.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(slug).fetch_all(c).await {
+            Ok(rows) => rows
+                .into_iter()
+                .map(|r| {
+                    let row = self.base.textify_row(r).0;
+                    (
+                        row.get("day").unwrap().parse::<u128>().unwrap_or(0),
+                        row.get("views").unwrap().parse::<i32>().unwrap_or(0),
+                        HyperLogLog::from_hex(row.get("visitors").unwrap()),
+                    )
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Check if a profile (or the holder of the edit password) can see the view analytics
+    /// of a post
+    ///
+    /// Owners, ACL admins and managers can, and so can anyone with the edit password.
+    /// Share links can't.
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `user` - the profile asking
+    /// * `password` - the edit password, blank to only check the profile
+    /// * `ip` - the IP address of the user giving the password
+    pub async fn can_view_stats(
+        &self,
+        post: &Post,
+        user: Option<&Profile>,
+        password: &str,
+        ip: &str,
+    ) -> Result<()> {
+        if let Some(ua) = user {
+            if self.has_post_role(post, ua, AclRole::Admin) || self.is_manager(ua).await {
+                return Ok(());
+            }
+        }
+
+        if password.is_empty() {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        match self.unlock_post(post, password, ip).await? {
+            None => Ok(()),
+            Some(_) => Err(DatabaseError::NotAllowed),
+        }
+    }
+
+    /// Get the view analytics of a post
+    ///
+    /// # Arguments
+    /// * `slug` - the slug of the post
+    /// * `days` - the number of days to include (up to a year)
+    pub async fn get_post_stats(&self, slug: String, days: u32) -> Result<PostStats> {
+        let days = days.clamp(1, 365) as u128;
+        let today = utility::unix_epoch_timestamp() / DAY;
+        let first = (today + 1).saturating_sub(days);
+
+        let mut buckets: BTreeMap<u128, (i32, HyperLogLog)> = BTreeMap::new();
+        for day in first..=today {
+            buckets.insert(day, (0, HyperLogLog::new()));
+        }
+
+        // stored stats
+        for (day, views, visitors) in self.get_stored_stats_by_slug(&slug).await {
+            if let Some(bucket) = buckets.get_mut(&day) {
+                bucket.0 += views;
+                bucket.1.merge(&visitors);
+            }
+        }
+
+        // stats we haven't flushed yet
+        for ((s, day), (views, visitors)) in self.pending_stats.lock().unwrap().iter() {
+            if s != &slug {
+                continue;
+            }
+
+            if let Some(bucket) = buckets.get_mut(day) {
+                bucket.0 += views;
+                bucket.1.merge(visitors);
+            }
+        }
+
+        // ...
+        let mut all_visitors = HyperLogLog::new();
+        for (_, visitors) in buckets.values() {
+            all_visitors.merge(visitors);
+        }
+
+        Ok(PostStats {
+            views: self.get_views_by_slug(slug).await,
+            unique_visitors: all_visitors.count(),
+            days: buckets
+                .into_iter()
+                .map(|(day, (views, visitors))| PostDayStats {
+                    day: day * DAY,
+                    views,
+                    unique_visitors: visitors.count(),
+                })
+                .collect(),
+        })
+    }
+
+    /// Write all pending daily stats to the database, returns the number of days updated
+    pub async fn flush_stats(&self) -> usize {
+        let pending: Vec<((String, u128), (i32, HyperLogLog))> =
+            self.pending_stats.lock().unwrap().drain().collect();
+        let mut flushed: usize = 0;
+
+        let c = &self.base.db.client;
+        for ((slug, day), (views, mut visitors)) in pending {
+            let stored = self
+                .get_stored_stats_by_slug(&slug)
+                .await
+                .into_iter()
+                .find(|s| s.0 == day);
+
+            let res = match stored {
+                Some((_, stored_views, stored_visitors)) => {
+                    // update existing day
+                    visitors.merge(&stored_visitors);
+
+                    let query: String = if (self.base.db.r#type == "sqlite")
+                        | (self.base.db.r#type == "mysql")
+                    {
+                        "UPDATE \":t\" SET \"views\" = ?, \"visitors\" = ? WHERE \"slug\" = ? AND \"day\" = ?"
+                    } else {
+                        "UPDATE \":t\" SET \"views\" = $1, \"visitors\" = $2 WHERE \"slug\" = $3 AND \"day\" = $4"
+                    }
+                    .to_string()
+                    .replace(":t", &self.config.table_stats.table_name);
+
+                    sqlquery(&query)
+                        .bind::<&String>(&(stored_views + views).to_string())
+                        .bind::<&String>(&visitors.to_hex())
+                        .bind::<&String>(&slug)
+                        .bind::<&String>(&day.to_string())
+                        .execute(c)
+                        .await
+                }
+                None => {
+                    // create day
+                    let query: String =
+                        if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+                            "INSERT INTO \":t\" VALUES (?, ?, ?, ?)"
+                        } else {
+                            "INSERT INTO \":t\" VALUES ($1, $2, $3, $4)"
+                        }
+                        .to_string()
+                        .replace(":t", &self.config.table_stats.table_name);
+
+                    sqlquery(&query)
+                        .bind::<&String>(&slug)
+                        .bind::<&String>(&day.to_string())
+                        .bind::<&String>(&views.to_string())
+                        .bind::<&String>(&visitors.to_hex())
+                        .execute(c)
+                        .await
+                }
+            };
+
+            match res {
+                Ok(_) => flushed += 1,
+                Err(_) => {
+                    // try again next time
+                    let mut pending = self.pending_stats.lock().unwrap();
+                    let entry = pending.entry((slug, day)).or_default();
+                    entry.0 += views;
+                    entry.1.merge(&visitors);
+                }
+            }
+        }
+
+        flushed
+    }
+
+    /// Move the daily stats of a renamed post to its new slug
+    ///
+    /// # Arguments
+    /// * `slug` - the old slug of the post
+    /// * `new_slug` - the new slug of the post
+    async fn move_stats(&self, slug: String, new_slug: String) {
+        {
+            let mut pending = self.pending_stats.lock().unwrap();
+            let keys: Vec<(String, u128)> =
+                pending.keys().filter(|k| k.0 == slug).cloned().collect();
+
+            for key in keys {
+                if let Some(stats) = pending.remove(&key) {
+                    pending.insert((new_slug.clone(), key.1), stats);
+                }
+            }
+        }
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "UPDATE \":t\" SET \"slug\" = ? WHERE \"slug\" = ?"
+        } else {
+            "UPDATE \":t\" SET \"slug\" = $1 WHERE \"slug\" = $2"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_stats.table_name);
+
+        let c = &self.base.db.client;
+        let _ = sqlquery(&query)
+            .bind::<&String>(&new_slug)
+            .bind::<&String>(&slug)
+            .execute(c)
+            .await;
+    }
+
+    /// Delete the daily stats of a post
+    ///
+    /// # Arguments
+    /// * `slug` - the slug of the post
+    pub async fn delete_stats_by_slug(&self, slug: String) -> Result<()> {
+        self.pending_stats
+            .lock()
+            .unwrap()
+            .retain(|(s, _), _| s != &slug);
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \":t\" WHERE \"slug\" = ?"
+        } else {
+            "DELETE FROM \":t\" WHERE \"slug\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_stats.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&slug).execute(c).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    // revisions
+
+    /// Get the value stored as the editor of a [`PostRevision`]
+    ///
+    /// # Arguments
+    /// * `user` - the user editing the post
+    /// * `ip` - the IP address of the user editing the post
+    fn revision_editor(&self, user: &Option<Box<Profile>>, ip: &str) -> String {
+        match user {
+            Some(ua) => ua.id.clone(),
+            None => {
+                // keyed, so the IPv4 space can't just be hashed to find the IP again
+                let mut mac = self.mac();
+                mac.update(format!("editor:{ip}").as_bytes());
+                hex::encode(mac.finalize().into_bytes())
+            }
+        }
+    }
+
+    /// Get a [`PostRevision`] from a database row
+    ///
+    /// # Arguments
+    /// * `res` - the row
+    fn gimme_revision(&self, res: BTreeMap<String, String>) -> Result<PostRevision> {
+        Ok(PostRevision {
+            id: res.get("id").unwrap().to_string(),
+            post: res.get("post").unwrap().to_string(),
+            timestamp: res.get("timestamp").unwrap().parse::<u128>().unwrap(),
+            editor: res.get("editor").unwrap().to_string(),
+            content: res.get("content").unwrap().to_string(),
+            context: match serde_json::from_str::<PostContext>(res.get("context").unwrap()) {
+                // revisions stored before view passwords were hashed may still have one
+                Ok(m) => PostContext {
+                    view_password: String::new(),
+                    ..m
+                },
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+        })
+    }
+
+    /// Get an existing revision
+    ///
+    /// # Arguments
+    /// * `id` - the ID of the revision
+    pub async fn get_revision(&self, id: String) -> Result<PostRevision> {
+        // check in cache
+        if let Some(c) = self
+            .base
+            .cachedb
+            .get(format!("{}:{}", self.config.table_revisions.prefix, id))
+            .await
+        {
+            return Ok(serde_json::from_str::<PostRevision>(c.as_str()).unwrap());
+        }
+
+        // pull from database
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \":t\" WHERE \"id\" = ?"
+        } else {
+            "SELECT * FROM \":t\" WHERE \"id\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_revisions.table_name);
+
+        let c = &self.base.db.client;
+        let revision = match sqlquery(&query).bind::<&String>(&id).fetch_one(c).await {
+            Ok(r) => match self.gimme_revision(self.base.textify_row(r).0) {
+                Ok(r) => r,
+                Err(e) => return Err(e),
+            },
+            Err(_) => return Err(DatabaseError::NotFound),
+        };
+
+        // store in cache
+        self.base
+            .cachedb
+            .set(
+                format!("{}:{}", self.config.table_revisions.prefix, id),
+                serde_json::to_string::<PostRevision>(&revision).unwrap(),
+            )
+            .await;
+
+        // return
+        Ok(revision)
+    }
+
+    /// Get all revisions of an existing post, newest first
+    ///
+    /// # Arguments
+    /// * `id` - the `id` of the post
+    pub async fn get_revisions_by_post(&self, id: String) -> Result<Vec<PostRevision>> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \":t\" WHERE \"post\" = ? ORDER BY \"timestamp\" DESC"
+        } else {
+            "SELECT * FROM \":t\" WHERE \"post\" = $1 ORDER BY \"timestamp\" DESC"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_revisions.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&id).fetch_all(c).await {
+            Ok(rows) => {
+                let mut out = Vec::new();
+
+                for row in rows {
+                    match self.gimme_revision(self.base.textify_row(row).0) {
+                        Ok(r) => out.push(r),
+                        Err(e) => return Err(e),
+                    }
+                }
+
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Store the current state of a post as a new revision
+    ///
+    /// # Arguments
+    /// * `post` - the post which is about to be edited
+    /// * `editor` - the profile ID or hashed IP of the user editing the post
+    pub async fn create_revision(&self, post: &Post, editor: String) -> Result<PostRevision> {
+        let revision = PostRevision {
+            id: utility::random_id(),
+            post: post.id.clone(),
+            timestamp: utility::unix_epoch_timestamp(),
+            editor,
+            content: post.content.clone(),
+            context: PostContext {
+                // revisions are public, view passwords aren't
+                view_password: String::new(),
+                ..post.context.clone()
+            },
+        };
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \":t\" VALUES (?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \":t\" VALUES ($1, $2, $3, $4, $5, $6)"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_revisions.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&String>(&revision.id)
+            .bind::<&String>(&revision.post)
+            .bind::<&String>(&revision.timestamp.to_string())
+            .bind::<&String>(&revision.editor)
+            .bind::<&String>(&revision.content)
+            .bind::<&String>(match serde_json::to_string(&revision.context) {
+                Ok(ref s) => s,
+                Err(_) => return Err(DatabaseError::ValueError),
+            })
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(revision),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Delete all revisions of an existing post
+    ///
+    /// # Arguments
+    /// * `id` - the `id` of the post
+    pub async fn delete_revisions_by_post(&self, id: String) -> Result<()> {
+        // remove from cache
+        for revision in self.get_revisions_by_post(id.clone()).await? {
+            self.base
+                .cachedb
+                .remove(format!(
+                    "{}:{}",
+                    self.config.table_revisions.prefix, revision.id
+                ))
+                .await;
+        }
+
+        // delete revisions
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \":t\" WHERE \"post\" = ?"
+        } else {
+            "DELETE FROM \":t\" WHERE \"post\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_revisions.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&id).execute(c).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Get the content of an existing post at the given revision
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `id` - the ID of the revision (blank for the current version)
+    pub async fn get_post_content_at(&self, post: &Post, id: String) -> Result<String> {
+        if id.is_empty() {
+            return Ok(post.content.clone());
+        }
+
+        match self.get_revision(id).await {
+            Ok(r) => {
+                if r.post != post.id {
+                    return Err(DatabaseError::NotFound);
+                }
+
+                Ok(r.content)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Diff two versions of an existing post
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `from` - the ID of the revision to diff from (blank for the current version)
+    /// * `to` - the ID of the revision to diff to (blank for the current version)
+    pub async fn diff_post(&self, post: &Post, from: String, to: String) -> Result<PostDiff> {
+        let old = self.get_post_content_at(post, from.clone()).await?;
+        let new = self.get_post_content_at(post, to.clone()).await?;
+        Ok(PostDiff::new(from, to, &old, &new))
+    }
+
+    // expiry
+
+    /// Remove every post which has passed its `expires_at` time
+    ///
+    /// # Returns
+    /// * the number of posts removed
+    pub async fn purge_expired_posts(&self) -> usize {
+        let query = expiring_posts_query(&self.config);
+
+        let c = &self.base.db.client;
+        let rows = match sqlquery(&query).fetch_all(c).await {
+            Ok(r) => r,
+            Err(_) => return 0,
+        };
+
+        let mut purged = 0;
+        for row in rows {
+            let row = self.base.textify_row(row).0;
+
+            let context: PostContext =
+                match serde_json::from_str(row.get(&self.config.table_posts.context).unwrap()) {
+                    Ok(m) => m,
+                    Err(_) => continue,
+                };
+
+            if !context.is_expired() {
+                continue;
+            }
+
+            // we only need the slug and id to remove the post
+            let post = Post {
+                id: row.get(&self.config.table_posts.id).unwrap().to_string(),
+                slug: row.get(&self.config.table_posts.slug).unwrap().to_string(),
+                content: String::new(),
+                password: String::new(),
+                date_published: 0,
+                date_edited: 0,
+                context,
+                ips: Vec::new(),
+            };
+
+            if self.remove_post(&post).await.is_ok() {
+                purged += 1;
+            }
+        }
+
+        purged
+    }
+
+    // aliases
+
+    /// Get the slug an old slug of a renamed post points to
+    ///
+    /// # Arguments
+    /// * `slug` - the old slug
+    pub async fn get_alias(&self, mut slug: String) -> Result<String> {
+        slug = idna::punycode::encode_str(&slug).unwrap().to_lowercase();
+
+        if slug.ends_with("-") {
+            slug.pop();
+        }
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \":t\" WHERE \"slug\" = ?"
+        } else {
+            "SELECT * FROM \":t\" WHERE \"slug\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_aliases.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&slug).fetch_one(c).await {
+            Ok(r) => Ok(self
+                .base
+                .textify_row(r)
+                .0
+                .get("target")
+                .unwrap()
+                .to_string()),
+            Err(_) => Err(DatabaseError::NotFound),
+        }
+    }
+
+    /// Point an old slug at the new slug of a renamed post
+    ///
+    /// # Arguments
+    /// * `slug` - the old slug
+    /// * `target` - the new slug
+    pub async fn create_alias(&self, slug: String, target: String) -> Result<()> {
+        let c = &self.base.db.client;
+
+        // the new slug can't be an alias anymore (the post was renamed back to it)
+        self.delete_alias(target.clone()).await?;
+
+        // aliases of the old slug now point to the new one, so we never redirect twice
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "UPDATE \":t\" SET \"target\" = ? WHERE \"target\" = ?"
+        } else {
+            "UPDATE \":t\" SET \"target\" = $1 WHERE \"target\" = $2"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_aliases.table_name);
+
+        if sqlquery(&query)
+            .bind::<&String>(&target)
+            .bind::<&String>(&slug)
+            .execute(c)
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        // create alias
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \":t\" VALUES (?, ?)"
+        } else {
+            "INSERT INTO \":t\" VALUES ($1, $2)"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_aliases.table_name);
+
+        match sqlquery(&query)
+            .bind::<&String>(&slug)
+            .bind::<&String>(&target)
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            // another request created the alias first
+            Err(e)
+                if e.as_database_error()
+                    .is_some_and(|e| e.is_unique_violation()) =>
+            {
+                Err(DatabaseError::MustBeUnique)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Delete an alias
+    ///
+    /// # Arguments
+    /// * `slug` - the old slug
+    pub async fn delete_alias(&self, slug: String) -> Result<()> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \":t\" WHERE \"slug\" = ?"
+        } else {
+            "DELETE FROM \":t\" WHERE \"slug\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_aliases.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&slug).execute(c).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Delete every alias pointing to a slug
+    ///
+    /// # Arguments
+    /// * `target` - the slug of the post
+    pub async fn delete_aliases_by_target(&self, target: String) -> Result<()> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \":t\" WHERE \"target\" = ?"
+        } else {
+            "DELETE FROM \":t\" WHERE \"target\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_aliases.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&target).execute(c).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    // shares
+
+    /// Create an HMAC keyed with the server's secret
+    fn mac(&self) -> Hmac<Sha256> {
+        Hmac::<Sha256>::new_from_slice(self.config.secret.as_bytes())
+            .expect("HMAC accepts keys of any size")
+    }
+
+    /// Create a [`Share`] from a database row
+    ///
+    /// # Arguments
+    /// * `res` - the row
+    fn gimme_share(&self, res: BTreeMap<String, String>) -> Result<Share> {
+        Ok(Share {
+            id: res.get("id").unwrap().to_string(),
+            post: res.get("post").unwrap().to_string(),
+            permission: match serde_json::from_str(res.get("permission").unwrap()) {
+                Ok(p) => p,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            timestamp: res.get("timestamp").unwrap().parse::<u128>().unwrap(),
+            expires_at: res.get("expires_at").unwrap().parse::<u128>().unwrap(),
+        })
+    }
+
+    /// Sign a share link, the signature covers everything the link allows
+    ///
+    /// # Arguments
+    /// * `share` - the share link
+    fn sign_share(&self, share: &Share) -> Hmac<Sha256> {
+        let mut mac = self.mac();
+
+        mac.update(
+            format!(
+                "{}:{}:{:?}:{}",
+                share.id, share.post, share.permission, share.expires_at
+            )
+            .as_bytes(),
+        );
+
+        mac
+    }
+
+    /// Get the token of a share link, given to the link's holder
+    ///
+    /// # Arguments
+    /// * `share` - the share link
+    pub fn share_token(&self, share: &Share) -> String {
+        format!(
+            "{}.{}",
+            share.id,
+            hex::encode(self.sign_share(share).finalize().into_bytes())
+        )
+    }
+
+    /// Check a share link token against a post
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `token` - the token of the share link
+    ///
+    /// # Returns
+    /// * `None` if the token is invalid, expired, revoked or for another post
+    pub async fn check_share(&self, post: &Post, token: &str) -> Option<SharePermission> {
+        let (id, signature) = token.split_once('.')?;
+        let signature = hex::decode(signature).ok()?;
+
+        let share = self.get_share(id.to_string()).await.ok()?;
+
+        if (share.post != post.id) | share.is_expired() {
+            return None;
+        }
+
+        // compared in constant time
+        self.sign_share(&share).verify_slice(&signature).ok()?;
+        Some(share.permission)
+    }
+
+    /// Get an existing share link by `id`
+    ///
+    /// # Arguments
+    /// * `id` - the ID of the share link
+    pub async fn get_share(&self, id: String) -> Result<Share> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \":t\" WHERE \"id\" = ?"
+        } else {
+            "SELECT * FROM \":t\" WHERE \"id\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_shares.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&id).fetch_one(c).await {
+            Ok(r) => self.gimme_share(self.base.textify_row(r).0),
+            Err(_) => Err(DatabaseError::NotFound),
+        }
+    }
+
+    /// Check if a profile can manage the share links of a post
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `user` - the profile
+    async fn check_share_manager(&self, post: &Post, user: &Profile) -> Result<()> {
+        if self.has_post_role(post, user, AclRole::Admin) {
+            return Ok(());
+        }
+
+        let group = match self.auth.get_group_by_id(user.group).await {
+            Ok(g) => g,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        if !group.permissions.check_manager() {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        if self
+            .auth
+            .audit(
+                user.id.to_owned(),
+                format!("Managed a post's share links: {}", post.slug),
+            )
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        Ok(())
+    }
+
+    /// Get all share links of an existing post, along with their tokens
+    ///
+    /// # Arguments
+    /// * `slug` - the post
+    /// * `user` - the profile listing the share links
+    pub async fn get_shares_by_post(
+        &self,
+        slug: String,
+        user: Box<Profile>,
+    ) -> Result<Vec<(String, Share)>> {
+        let post = self.get_post(slug).await?;
+        self.check_share_manager(&post, &user).await?;
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \":t\" WHERE \"post\" = ? ORDER BY \"timestamp\" DESC"
+        } else {
+            "SELECT * FROM \":t\" WHERE \"post\" = $1 ORDER BY \"timestamp\" DESC"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_shares.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&String>(&post.id)
+            .fetch_all(c)
+            .await
+        {
+            Ok(rows) => {
+                let mut out = Vec::new();
+
+                for row in rows {
+                    let share = self.gimme_share(self.base.textify_row(row).0)?;
+                    out.push((self.share_token(&share), share));
+                }
+
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Create a new share link for an existing post
+    ///
+    /// # Arguments
+    /// * `slug` - the post
+    /// * `props` - [`CreateShare`]
+    /// * `user` - the profile creating the share link
+    ///
+    /// # Returns
+    /// * `(token, share)`
+    pub async fn create_share(
+        &self,
+        slug: String,
+        props: CreateShare,
+        user: Box<Profile>,
+    ) -> Result<(String, Share)> {
+        let post = self.get_post(slug).await?;
+        self.check_share_manager(&post, &user).await?;
+
+        let timestamp = utility::unix_epoch_timestamp();
+        let share = Share {
+            id: utility::random_id(),
+            post: post.id,
+            permission: props.permission,
+            timestamp,
+            expires_at: if props.hours == 0 {
+                0
+            } else {
+                timestamp + (props.hours as u128 * 60 * 60 * 1000)
+            },
+        };
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \":t\" VALUES (?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \":t\" VALUES ($1, $2, $3, $4, $5)"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_shares.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&String>(&share.id)
+            .bind::<&String>(&share.post)
+            .bind::<&String>(match serde_json::to_string(&share.permission) {
+                Ok(ref p) => p,
+                Err(_) => return Err(DatabaseError::ValueError),
+            })
+            .bind::<&String>(&share.timestamp.to_string())
+            .bind::<&String>(&share.expires_at.to_string())
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok((self.share_token(&share), share)),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Revoke a share link of an existing post
+    ///
+    /// # Arguments
+    /// * `slug` - the post
+    /// * `id` - the ID of the share link
+    /// * `user` - the profile revoking the share link
+    pub async fn delete_share(&self, slug: String, id: String, user: Box<Profile>) -> Result<()> {
+        let post = self.get_post(slug).await?;
+        self.check_share_manager(&post, &user).await?;
+
+        if self.get_share(id.clone()).await?.post != post.id {
+            return Err(DatabaseError::NotFound);
+        }
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \":t\" WHERE \"id\" = ?"
+        } else {
+            "DELETE FROM \":t\" WHERE \"id\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_shares.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&id).execute(c).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Delete every share link of a post
+    ///
+    /// # Arguments
+    /// * `id` - the `id` of the post
+    pub async fn delete_shares_by_post(&self, id: String) -> Result<()> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \":t\" WHERE \"post\" = ?"
+        } else {
+            "DELETE FROM \":t\" WHERE \"post\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_shares.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&id).execute(c).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    // reports
+
+    /// Check if a profile is a manager
+    ///
+    /// # Arguments
+    /// * `user` - the profile
+    pub async fn is_manager(&self, user: &Profile) -> bool {
+        match self.auth.get_group_by_id(user.group).await {
+            Ok(g) => g.permissions.check_manager(),
+            Err(_) => false,
+        }
+    }
+
+    /// Create a [`Report`] from a database row
+    ///
+    /// # Arguments
+    /// * `res` - the row
+    fn gimme_report(&self, res: BTreeMap<String, String>) -> Result<Report> {
+        Ok(Report {
+            id: res.get("id").unwrap().to_string(),
+            post: res.get("post").unwrap().to_string(),
+            reason: res.get("reason").unwrap().to_string(),
+            reporter: res.get("reporter").unwrap().to_string(),
+            ip: res.get("ip").unwrap().to_string(),
+            timestamp: res.get("timestamp").unwrap().parse::<u128>().unwrap(),
+        })
+    }
+
+    /// Report an existing post
+    ///
+    /// # Arguments
+    /// * `slug` - the post
+    /// * `props` - [`CreateReport`]
+    /// * `ip` - the IP the report was sent from
+    /// * `user` - the profile sending the report
+    pub async fn create_report(
+        &self,
+        slug: String,
+        props: CreateReport,
+        ip: String,
+        user: Option<Box<Profile>>,
+    ) -> Result<Report> {
+        let post = self.get_post(slug).await?;
+
+        // check lengths
+        let reason = props.reason.trim().to_string();
+
+        if reason.is_empty() {
+            return Err(DatabaseError::ContentTooShort);
+        }
+
+        if reason.len() > 1000 {
+            return Err(DatabaseError::ContentTooLong);
+        }
+
+        let report = Report {
+            id: utility::random_id(),
+            post: post.id,
+            reason,
+            reporter: match user {
+                Some(ua) => ua.id,
+                None => String::new(),
+            },
+            ip,
+            timestamp: utility::unix_epoch_timestamp(),
+        };
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \":t\" VALUES (?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \":t\" VALUES ($1, $2, $3, $4, $5, $6)"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_reports.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&String>(&report.id)
+            .bind::<&String>(&report.post)
+            .bind::<&String>(&report.reason)
+            .bind::<&String>(&report.reporter)
+            .bind::<&String>(&report.ip)
+            .bind::<&String>(&report.timestamp.to_string())
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(report),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Get all reports along with the posts they're about, oldest first
+    ///
+    /// # Arguments
+    /// * `page` - the page number
+    /// * `user` - the manager reviewing the reports
+    ///
+    /// # Returns
+    /// * `(reports, has_next)`, `has_next` counts every row fetched, so it stays right
+    ///   when the posts of some reports are gone
+    pub async fn get_reports(
+        &self,
+        page: u32,
+        user: Box<Profile>,
+    ) -> Result<(Vec<(Report, Post)>, bool)> {
+        if !self.is_manager(&user).await {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        let query: String = format!(
+            "SELECT * FROM \":t\" ORDER BY \"timestamp\" ASC LIMIT {POSTS_PER_PAGE} OFFSET {}",
+            page_offset(page)
+        )
+        .replace(":t", &self.config.table_reports.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).fetch_all(c).await {
+            Ok(rows) => {
+                let has_next = rows.len() == POSTS_PER_PAGE as usize;
+                let mut out = Vec::new();
+
+                for row in rows {
+                    let report = self.gimme_report(self.base.textify_row(row).0)?;
+
+                    // the post may have been removed since
+                    if let Ok(post) = self.get_post_by_id(report.post.clone()).await {
+                        out.push((report, post));
+                    }
+                }
+
+                Ok((out, has_next))
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Dismiss a report
+    ///
+    /// # Arguments
+    /// * `id` - the ID of the report
+    /// * `user` - the manager dismissing the report
+    pub async fn delete_report(&self, id: String, user: Box<Profile>) -> Result<()> {
+        if !self.is_manager(&user).await {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        if self
+            .auth
+            .audit(user.id.to_owned(), format!("Dismissed a report: {id}"))
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \":t\" WHERE \"id\" = ?"
+        } else {
+            "DELETE FROM \":t\" WHERE \"id\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_reports.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&id).execute(c).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Delete every report of a post
+    ///
+    /// # Arguments
+    /// * `id` - the `id` of the post
+    pub async fn delete_reports_by_post(&self, id: String) -> Result<()> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \":t\" WHERE \"post\" = ?"
+        } else {
+            "DELETE FROM \":t\" WHERE \"post\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_reports.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&id).execute(c).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Remove the content of a post, leaving a tombstone which keeps its slug reserved,
+    /// without checking any permissions
+    ///
+    /// Revisions, share links and reports of the post are removed along with its content.
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    /// * `reason` - why the post was taken down
+    /// * `legal` - if the post was taken down for legal reasons
+    pub async fn take_down_post(&self, mut post: Post, reason: String, legal: bool) -> Result<()> {
+        post.content = String::new();
+        post.context.moderation = Moderation::TakenDown { reason, legal };
+        // tombstones never go away on their own
+        post.context.expires_at = 0;
+        post.context.burn_after_read = false;
+        post.context.tags = Vec::new();
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "UPDATE \":t\" SET \":content\" = ?, \":metadata\" = ? WHERE \":slug\" = ?"
+        } else {
+            "UPDATE \":t\" SET \":content\" = $1, \":metadata\" = $2 WHERE \":slug\" = $3"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_posts.table_name)
+        .replace(":slug", &self.config.table_posts.slug)
+        .replace(":content", &self.config.table_posts.content)
+        .replace(":metadata", &self.config.table_posts.context);
+
+        let c = &self.base.db.client;
+        if sqlquery(&query)
+            .bind::<&String>(&post.content)
+            .bind::<&String>(match serde_json::to_string(&post.context) {
+                Ok(ref m) => m,
+                Err(_) => return Err(DatabaseError::ValueError),
+            })
+            .bind::<&String>(&post.slug)
+            .execute(c)
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        // remove from cache
+        self.base
+            .cachedb
+            .remove(format!("{}:{}", self.config.table_posts.prefix, post.slug))
+            .await;
+
+        // nothing of the old content should be left
+        self.delete_revisions_by_post(post.id.clone()).await?;
+        self.delete_shares_by_post(post.id.clone()).await?;
+        self.delete_reports_by_post(post.id.clone()).await?;
+        self.delete_tags_by_post(post.id.clone()).await?;
+        self.unindex_post(post.id).await
+    }
+
+    /// Act on a reported post as a manager
+    ///
+    /// # Arguments
+    /// * `slug` - the post
+    /// * `props` - [`ModeratePost`]
+    /// * `user` - the manager
+    pub async fn moderate_post(
+        &self,
+        slug: String,
+        props: ModeratePost,
+        user: Box<Profile>,
+    ) -> Result<()> {
+        if !self.is_manager(&user).await {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        let mut post = self.get_post(slug).await?;
+
+        let action = match props.action {
+            ModAction::Hide => "Hid a post",
+            ModAction::Restore => "Restored a post",
+            ModAction::TakeDown => "Took down a post",
+            ModAction::Delete => "Deleted a post",
+            ModAction::BanIp => "Banned the IP of a post",
+        };
+
+        if self
+            .auth
+            .audit(user.id.to_owned(), format!("{action}: {}", post.slug))
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        match props.action {
+            ModAction::Hide => {
+                post.context.moderation = Moderation::Hidden;
+                self.update_post_context(&post.slug, &post.context).await?;
+                self.unindex_post(post.id).await
+            }
+            ModAction::Restore => {
+                post.context.moderation = Moderation::Active;
+                self.update_post_context(&post.slug, &post.context).await?;
+                self.sync_search_index(&post.slug).await
+            }
+            ModAction::TakeDown => self.take_down_post(post, props.reason, props.legal).await,
+            ModAction::Delete => self.remove_post(&post).await,
+            ModAction::BanIp => {
+                // the other IPs are those of later editors (collaborators and share links)
+                let ip = match post.ips.first() {
+                    Some((_, ip)) if !ip.is_empty() => ip.to_owned(),
+                    _ => return Err(DatabaseError::NotFound),
+                };
+
+                match self
+                    .auth
+                    .create_ipban(
+                        IpBanCreate {
+                            ip,
+                            reason: props.reason,
+                        },
+                        user,
+                    )
+                    .await
+                {
+                    // already banned
+                    Ok(_) | Err(AuthError::MustBeUnique) => Ok(()),
+                    Err(AuthError::NotAllowed) => Err(DatabaseError::NotAllowed),
+                    Err(_) => Err(DatabaseError::Other),
+                }
+            }
+        }
+    }
+
+    // search
+
+    /// Add a post to the search index (or update it), removing it instead if it
+    /// isn't searchable
+    ///
+    /// # Arguments
+    /// * `post` - the post
+    pub async fn index_post(&self, post: &Post) -> Result<()> {
+        self.unindex_post(post.id.clone()).await?;
+
+        if !post.is_searchable() {
+            return Ok(());
+        }
+
+        let query =
+            search::insert_query(&self.base.db.r#type, &self.config.table_search.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&String>(&post.id)
+            .bind::<&String>(&post.slug)
+            .bind::<&String>(&post.context.title)
+            .bind::<&String>(&post.context.description)
+            .bind::<&String>(&post.content)
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Update a post in the search index after it changed
+    ///
+    /// # Arguments
+    /// * `slug` - the post
+    pub async fn sync_search_index(&self, slug: &String) -> Result<()> {
+        let post = self.get_post(slug.to_owned()).await?;
+        self.index_post(&post).await
+    }
+
+    /// Remove a post from the search index
+    ///
+    /// # Arguments
+    /// * `id` - the `id` of the post
+    pub async fn unindex_post(&self, id: String) -> Result<()> {
+        let query =
+            search::delete_query(&self.base.db.r#type, &self.config.table_search.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&id).execute(c).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Index every post if the search index is empty
+    ///
+    /// # Returns
+    /// * the number of posts indexed
+    pub async fn backfill_search_index(&self) -> Result<usize> {
+        let c = &self.base.db.client;
+
+        let query = format!(
+            "SELECT id FROM \"{}\" LIMIT 1",
+            self.config.table_search.table_name
+        );
+
+        if sqlquery(&query).fetch_one(c).await.is_ok() {
+            // already indexed
+            return Ok(0);
+        }
+
+        let query: String = "SELECT * FROM \":t\""
+            .to_string()
+            .replace(":t", &self.config.table_posts.table_name);
+
+        let rows = match sqlquery(&query).fetch_all(c).await {
+            Ok(r) => r,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        let mut indexed = 0;
+        for row in rows {
+            let post = self.gimme_post(self.base.textify_row(row).0)?;
+
+            if post.is_searchable() && !post.is_expired() {
+                self.index_post(&post).await?;
+                indexed += 1;
+            }
+        }
+
+        Ok(indexed)
+    }
+
+    /// Search the content, title and description of every searchable post
+    ///
+    /// # Arguments
+    /// * `q` - the words to search for
+    /// * `page` - the page number
+    ///
+    /// # Returns
+    /// * `(posts, has_next)`, `has_next` counts every row fetched, so it stays right
+    ///   when some of them are left out
+    pub async fn search_posts(&self, q: String, page: u32) -> Result<(Vec<Post>, bool)> {
+        let param = search::search_param(&self.base.db.r#type, &q);
+
+        if param.is_empty() {
+            return Ok((Vec::new(), false));
+        }
+
+        let query = search::search_query(
+            &self.base.db.r#type,
+            &self.config.table_search.table_name,
+            page,
+        );
+
+        let c = &self.base.db.client;
+        let rows = match sqlquery(&query).bind::<&String>(&param).fetch_all(c).await {
+            Ok(r) => r,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        let has_next = rows.len() == POSTS_PER_PAGE as usize;
+
+        let mut out = Vec::new();
+        for row in rows {
+            let row = self.base.textify_row(row).0;
+
+            // the index is only a copy, so the post is checked again
+            match self.get_post(row.get("slug").unwrap().to_string()).await {
+                Ok(post) if post.is_searchable() => out.push(post),
+                _ => continue,
+            }
+        }
+
+        Ok((out, has_next))
+    }
+
+    // tags
+
+    /// Replace the tags of a post in the tag index
+    ///
+    /// # Arguments
+    /// * `id` - the `id` of the post
+    /// * `tags` - the normalised tags of the post
+    pub async fn set_post_tags(&self, id: String, tags: &Vec<String>) -> Result<()> {
+        self.delete_tags_by_post(id.clone()).await?;
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \":t\" VALUES (?, ?)"
+        } else {
+            "INSERT INTO \":t\" VALUES ($1, $2)"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_tags.table_name);
+
+        let c = &self.base.db.client;
+        for tag in tags {
+            if sqlquery(&query)
+                .bind::<&String>(tag)
+                .bind::<&String>(&id)
+                .execute(c)
+                .await
+                .is_err()
+            {
+                return Err(DatabaseError::Other);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove every tag of a post from the tag index
+    ///
+    /// # Arguments
+    /// * `id` - the `id` of the post
+    pub async fn delete_tags_by_post(&self, id: String) -> Result<()> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \":t\" WHERE \"post\" = ?"
+        } else {
+            "DELETE FROM \":t\" WHERE \"post\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_tags.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&id).execute(c).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Get the posts with a tag, newest first
+    ///
+    /// Only public posts are listed, along with the posts `user` has a role on.
+    ///
+    /// # Arguments
+    /// * `tag` - the tag (normalised before it's looked up)
+    /// * `page` - the page number
+    /// * `user` - the profile browsing the tag
+    ///
+    /// # Returns
+    /// * `(posts, has_next)`, `has_next` counts every row fetched, so it stays right
+    ///   when some of them are left out
+    pub async fn get_posts_by_tag(
+        &self,
+        tag: String,
+        page: u32,
+        user: Option<&Profile>,
+    ) -> Result<(Vec<Post>, bool)> {
+        let tag = match tags::normalize_tag(&tag) {
+            Some(t) => t,
+            None => return Err(DatabaseError::NotFound),
+        };
+
+        let query = posts_by_tag_query(&self.base.db.r#type, &self.config, page);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&tag).fetch_all(c).await {
+            Ok(rows) => {
+                let has_next = rows.len() == POSTS_PER_PAGE as usize;
+                let mut out = Vec::new();
+
+                for row in rows {
+                    let post = self.gimme_post(self.base.textify_row(row).0)?;
+
+                    if post.is_expired() | post.check_moderation().is_err() {
+                        continue;
+                    }
+
+                    if !post.is_searchable()
+                        && !user.is_some_and(|ua| self.has_post_role(&post, ua, AclRole::Viewer))
+                    {
+                        continue;
+                    }
+
+                    out.push(post);
+                }
+
+                Ok((out, has_next))
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Suggest existing tags starting with what a user typed, most used first
+    ///
+    /// # Arguments
+    /// * `q` - the start of the tag
+    pub async fn suggest_tags(&self, q: String) -> Result<Vec<TagCount>> {
+        // normalised tags never contain `%` or `_`, so this can't widen the pattern
+        let prefix = tags::normalize_tag(&q).unwrap_or_default();
+
+        let query = suggest_tags_query(&self.base.db.r#type, &self.config);
+
+        let c = &self.base.db.client;
+        let rows = match sqlquery(&query)
+            .bind::<&String>(&format!("{prefix}%"))
+            .fetch_all(c)
+            .await
+        {
+            Ok(r) => r,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        let mut out = Vec::new();
+        for row in rows {
+            let row = self.base.textify_row(row).0;
+
+            out.push(TagCount {
+                tag: row.get("tag").unwrap().to_string(),
+                posts: row.get("posts").unwrap().parse::<i32>().unwrap_or(0),
+            });
+        }
+
+        Ok(out)
+    }
+
+    // collections
+
+    /// Create a [`Collection`] from a database row
+    ///
+    /// # Arguments
+    /// * `res` - the row
+    fn gimme_collection(&self, res: BTreeMap<String, String>) -> Result<Collection> {
+        Ok(Collection {
+            id: res.get("id").unwrap().to_string(),
+            title: res.get("title").unwrap().to_string(),
+            owner: res.get("owner").unwrap().to_string(),
+            posts: match serde_json::from_str(res.get("posts").unwrap()) {
+                Ok(p) => p,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            date_created: res.get("date_created").unwrap().parse::<u128>().unwrap(),
+            date_edited: res.get("date_edited").unwrap().parse::<u128>().unwrap(),
+        })
+    }
+
+    /// Get an existing collection
+    ///
+    /// # Arguments
+    /// * `id` - the ID of the collection
+    pub async fn get_collection(&self, id: String) -> Result<Collection> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \":t\" WHERE \"id\" = ?"
+        } else {
+            "SELECT * FROM \":t\" WHERE \"id\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_collections.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&id).fetch_one(c).await {
+            Ok(r) => self.gimme_collection(self.base.textify_row(r).0),
+            Err(_) => Err(DatabaseError::NotFound),
+        }
+    }
+
+    /// Get all collections owned by a profile
+    ///
+    /// # Arguments
+    /// * `owner` - the ID of the profile
+    pub async fn get_collections_by_owner(&self, owner: String) -> Result<Vec<Collection>> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \":t\" WHERE \"owner\" = ? ORDER BY \"date_created\" DESC"
+        } else {
+            "SELECT * FROM \":t\" WHERE \"owner\" = $1 ORDER BY \"date_created\" DESC"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_collections.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&String>(&owner).fetch_all(c).await {
+            Ok(rows) => {
+                let mut out = Vec::new();
+
+                for row in rows {
+                    out.push(self.gimme_collection(self.base.textify_row(row).0)?);
+                }
+
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Get all collections a post is in
+    ///
+    /// # Arguments
+    /// * `slug` - the slug of the post
+    pub async fn get_collections_by_post(&self, slug: String) -> Result<Vec<Collection>> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \":t\" WHERE \"posts\" LIKE ?"
+        } else {
+            "SELECT * FROM \":t\" WHERE \"posts\" LIKE $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_collections.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&String>(&format!("%\"{slug}\"%"))
+            .fetch_all(c)
+            .await
+        {
+            Ok(rows) => {
+                let mut out = Vec::new();
+
+                for row in rows {
+                    let collection = self.gimme_collection(self.base.textify_row(row).0)?;
+
+                    // `_` in slugs matches any character in `LIKE`
+                    if collection.posts.contains(&slug) {
+                        out.push(collection);
+                    }
+                }
+
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Get the posts of a collection a profile (or a visitor without one) can view,
+    /// in order
+    ///
+    /// # Arguments
+    /// * `collection` - the collection
+    /// * `user` - the profile viewing the collection
+    pub async fn get_collection_posts(
+        &self,
+        collection: &Collection,
+        user: Option<&Profile>,
+    ) -> Vec<Post> {
+        let mut out = Vec::new();
+
+        for slug in &collection.posts {
+            let post = match self.get_post(slug.to_owned()).await {
+                Ok(p) => p,
+                Err(_) => continue,
+            };
+
+            if post.check_moderation().is_err() | !self.can_view_post(&post, user) {
+                continue;
+            }
+
+            out.push(post);
+        }
+
+        out
+    }
+
+    /// Check if a profile can manage a collection
+    ///
+    /// # Arguments
+    /// * `collection` - the collection
+    /// * `user` - the profile
+    async fn check_collection_owner(&self, collection: &Collection, user: &Profile) -> Result<()> {
+        if collection.owner == user.id {
+            return Ok(());
+        }
+
+        if !self.is_manager(user).await {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        if self
+            .auth
+            .audit(
+                user.id.to_owned(),
+                format!("Managed a collection: {}", collection.id),
+            )
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        Ok(())
+    }
+
+    /// Get a post which is about to be added to a collection, only editors of a post
+    /// can add it to a collection
+    ///
+    /// # Arguments
+    /// * `slug` - the slug of the post
+    /// * `user` - the profile adding the post
+    async fn get_collection_candidate(&self, slug: String, user: &Profile) -> Result<Post> {
+        let post = self.get_post(slug).await?;
+
+        if post.is_taken_down() {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        if !self.has_post_role(&post, user, AclRole::Editor) && !self.is_manager(user).await {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        Ok(post)
+    }
+
+    /// Write the title and posts of an existing collection
+    ///
+    /// # Arguments
+    /// * `collection` - the collection
+    async fn update_collection(&self, collection: &Collection) -> Result<()> {
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "UPDATE \":t\" SET \"title\" = ?, \"posts\" = ?, \"date_edited\" = ? WHERE \"id\" = ?"
+        } else {
+            "UPDATE \":t\" SET \"title\" = $1, \"posts\" = $2, \"date_edited\" = $3 WHERE \"id\" = $4"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_collections.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&String>(&collection.title)
+            .bind::<&String>(match serde_json::to_string(&collection.posts) {
+                Ok(ref p) => p,
+                Err(_) => return Err(DatabaseError::ValueError),
+            })
+            .bind::<&String>(&utility::unix_epoch_timestamp().to_string())
+            .bind::<&String>(&collection.id)
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Create a new collection
+    ///
+    /// # Arguments
+    /// * `props` - [`CreateCollection`]
+    /// * `user` - the profile creating the collection, which owns it
+    pub async fn create_collection(
+        &self,
+        props: CreateCollection,
+        user: Box<Profile>,
+    ) -> Result<Collection> {
+        if (props.title.len() > 250) | props.title.trim().is_empty() {
+            return Err(DatabaseError::ValueError);
+        }
+
+        if props.posts.len() > MAX_COLLECTION_POSTS {
+            return Err(DatabaseError::ValueError);
+        }
+
+        let mut posts: Vec<String> = Vec::new();
+        for slug in props.posts {
+            let post = self.get_collection_candidate(slug, &user).await?;
+
+            if !posts.contains(&post.slug) {
+                posts.push(post.slug);
+            }
+        }
+
+        let timestamp = utility::unix_epoch_timestamp();
+        let collection = Collection {
+            id: utility::random_id().chars().take(10).collect(),
+            title: props.title,
+            owner: user.id.clone(),
+            posts,
+            date_created: timestamp,
+            date_edited: timestamp,
+        };
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \":t\" VALUES (?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \":t\" VALUES ($1, $2, $3, $4, $5, $6)"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_collections.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&String>(&collection.id)
+            .bind::<&String>(&collection.title)
+            .bind::<&String>(&collection.owner)
+            .bind::<&String>(match serde_json::to_string(&collection.posts) {
+                Ok(ref p) => p,
+                Err(_) => return Err(DatabaseError::ValueError),
+            })
+            .bind::<&String>(&collection.date_created.to_string())
+            .bind::<&String>(&collection.date_edited.to_string())
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(collection),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Change the title of an existing collection
+    ///
+    /// # Arguments
+    /// * `id` - the ID of the collection
+    /// * `props` - [`EditCollection`]
+    /// * `user` - the profile editing the collection
+    pub async fn edit_collection(
+        &self,
+        id: String,
+        props: EditCollection,
+        user: Box<Profile>,
+    ) -> Result<()> {
+        let mut collection = self.get_collection(id).await?;
+        self.check_collection_owner(&collection, &user).await?;
+
+        if (props.title.len() > 250) | props.title.trim().is_empty() {
+            return Err(DatabaseError::ValueError);
+        }
+
+        collection.title = props.title;
+        self.update_collection(&collection).await
+    }
+
+    /// Delete an existing collection (its posts are left alone)
+    ///
+    /// # Arguments
+    /// * `id` - the ID of the collection
+    /// * `user` - the profile deleting the collection
+    pub async fn delete_collection(&self, id: String, user: Box<Profile>) -> Result<()> {
+        let collection = self.get_collection(id).await?;
+        self.check_collection_owner(&collection, &user).await?;
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \":t\" WHERE \"id\" = ?"
+        } else {
+            "DELETE FROM \":t\" WHERE \"id\" = $1"
+        }
+        .to_string()
+        .replace(":t", &self.config.table_collections.table_name);
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&String>(&collection.id)
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Add a post to an existing collection
+    ///
+    /// # Arguments
+    /// * `id` - the ID of the collection
+    /// * `props` - [`InsertCollectionPost`]
+    /// * `user` - the profile adding the post
+    ///
+    /// # Returns
+    /// * `DatabaseError::AlreadyExists` if the post is already in the collection
+    pub async fn insert_collection_post(
+        &self,
+        id: String,
+        props: InsertCollectionPost,
+        user: Box<Profile>,
+    ) -> Result<()> {
+        let mut collection = self.get_collection(id).await?;
+        self.check_collection_owner(&collection, &user).await?;
+
+        let post = self.get_collection_candidate(props.slug, &user).await?;
+
+        if collection.posts.contains(&post.slug) {
+            return Err(DatabaseError::AlreadyExists);
+        }
+
+        if collection.posts.len() >= MAX_COLLECTION_POSTS {
+            return Err(DatabaseError::ValueError);
+        }
+
+        match props.index {
+            Some(i) if i > collection.posts.len() => return Err(DatabaseError::ValueError),
+            Some(i) => collection.posts.insert(i, post.slug),
+            None => collection.posts.push(post.slug),
+        }
+
+        self.update_collection(&collection).await
+    }
+
+    /// Remove a post from an existing collection
+    ///
+    /// # Arguments
+    /// * `id` - the ID of the collection
+    /// * `slug` - the slug of the post
+    /// * `user` - the profile removing the post
+    pub async fn remove_collection_post(
+        &self,
+        id: String,
+        slug: String,
+        user: Box<Profile>,
+    ) -> Result<()> {
+        let mut collection = self.get_collection(id).await?;
+        self.check_collection_owner(&collection, &user).await?;
+
+        let count = collection.posts.len();
+        collection.posts.retain(|s| *s != slug);
+
+        if collection.posts.len() == count {
+            return Err(DatabaseError::NotFound);
+        }
+
+        self.update_collection(&collection).await
+    }
+
+    /// Change the order of the posts of an existing collection
+    ///
+    /// # Arguments
+    /// * `id` - the ID of the collection
+    /// * `posts` - every slug of the collection, in their new order
+    /// * `user` - the profile reordering the collection
+    ///
+    /// # Returns
+    /// * `DatabaseError::ValueError` if `posts` isn't the same set of slugs as the collection
+    pub async fn reorder_collection(
+        &self,
+        id: String,
+        posts: Vec<String>,
+        user: Box<Profile>,
+    ) -> Result<()> {
+        let mut collection = self.get_collection(id).await?;
+        self.check_collection_owner(&collection, &user).await?;
+
+        let mut current = collection.posts.clone();
+        let mut new = posts.clone();
+        current.sort();
+        new.sort();
+
+        if current != new {
+            // posts are only added and removed through their own endpoints
+            return Err(DatabaseError::ValueError);
+        }
+
+        collection.posts = posts;
+        self.update_collection(&collection).await
+    }
+
+    /// Point the collections of a renamed post at its new slug
+    ///
+    /// # Arguments
+    /// * `slug` - the old slug of the post
+    /// * `new_slug` - the new slug of the post
+    async fn rename_collection_post(&self, slug: String, new_slug: String) -> Result<()> {
+        for mut collection in self.get_collections_by_post(slug.clone()).await? {
+            for member in collection.posts.iter_mut() {
+                if *member == slug {
+                    *member = new_slug.clone();
+                }
+            }
+
+            self.update_collection(&collection).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove a deleted post from every collection it was in
+    ///
+    /// # Arguments
+    /// * `slug` - the slug of the post
+    async fn remove_post_from_collections(&self, slug: String) -> Result<()> {
+        for mut collection in self.get_collections_by_post(slug.clone()).await? {
+            collection.posts.retain(|s| *s != slug);
+            self.update_collection(&collection).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Check if an edit can change a post's slug or edit password
+///
+/// Renaming a post and changing its password are admin actions, so only the edit
+/// password, ACL admins (and the owner) and managers can do them.
+///
+/// # Arguments
+/// * `role` - the role of the editor on the post, `None` if the edit password was checked
+/// * `shared` - if the post was unlocked by a share link
+/// * `manager` - if the editor is a manager
+fn can_change_slug_or_password(role: Option<AclRole>, shared: bool, manager: bool) -> bool {
+    // editors skip the edit password, so they only get what editing the content needs
+    !shared && (manager || (role != Some(AclRole::Editor)))
+}
+
+/// Check if a post's view password still has to be given before it can be read
+///
+/// # Arguments
+/// * `post` - the post
+/// * `enabled` - if view passwords are enabled, see [`Config::view_password`]
+/// * `is_viewer` - if the user is on the post's access list or has a share link
+/// * `unlocked` - if the user has a valid unlock token
+fn needs_view_password(post: &Post, enabled: bool, is_viewer: bool, unlocked: bool) -> bool {
+    enabled && !post.context.view_password.is_empty() && !is_viewer && !unlocked
+}
+
+// view queries
+
+/// Build the query selecting every logged view of a post
+///
+/// # Arguments
+/// * `db_type` - the type of the database
+/// * `table` - the name of the views table
+fn views_by_slug_query(db_type: &str, table: &str) -> String {
+    if (db_type == "sqlite") | (db_type == "mysql") {
+        "SELECT * FROM \":t\" WHERE \"slug\" = ?"
+    } else {
+        "SELECT * FROM \":t\" WHERE \"slug\" = $1"
+    }
+    .to_string()
+    .replace(":t", table)
+}
+
+/// Build the query selecting a user's logged view of a post
+///
+/// # Arguments
+/// * `db_type` - the type of the database
+/// * `table` - the name of the views table
+fn view_by_user_query(db_type: &str, table: &str) -> String {
+    if (db_type == "sqlite") | (db_type == "mysql") {
+        "SELECT * FROM \":t\" WHERE \"slug\" = ? AND \"id\" = ?"
+    } else {
+        "SELECT * FROM \":t\" WHERE \"slug\" = $1 AND \"id\" = $2"
+    }
+    .to_string()
+    .replace(":t", table)
+}
+
+/// Build the query logging a user's view of a post
+///
+/// # Arguments
+/// * `db_type` - the type of the database
+/// * `table` - the name of the views table
+fn create_view_query(db_type: &str, table: &str) -> String {
+    if (db_type == "sqlite") | (db_type == "mysql") {
+        "INSERT INTO \":t\" VALUES (?, ?)"
+    } else {
+        "INSERT INTO \":t\" VALUES ($1, $2)"
+    }
+    .to_string()
+    .replace(":t", table)
+}
+
+/// Build the query adding the bound number of views to a post's stored view count
+///
+/// # Arguments
+/// * `db_type` - the type of the database
+/// * `table` - the name of the view counts table
+fn increment_view_count_query(db_type: &str, table: &str) -> String {
+    let (int, text) = if db_type == "mysql" {
+        ("SIGNED", "CHAR")
+    } else {
+        ("INTEGER", "TEXT")
+    };
+
+    let (count, slug) = if (db_type == "sqlite") | (db_type == "mysql") {
+        ("?", "?")
+    } else {
+        ("$1", "$2")
+    };
+
+    format!(
+        "UPDATE \"{table}\" SET \"count\" = CAST(CAST(\"count\" AS {int}) + CAST({count} AS {int}) AS {text}) WHERE \"slug\" = {slug}"
+    )
+}
+
+/// Build the statement adding a unique index to a table
+///
+/// # Arguments
+/// * `db_type` - the type of the database
+/// * `table` - the name of the table
+/// * `columns` - the columns which must be unique together
+fn unique_index_query(db_type: &str, table: &str, columns: &[&str]) -> String {
+    let name = format!("{table}_{}_unique", columns.join("_"));
+
+    if db_type == "mysql" {
+        // mysql can only index the start of a TEXT column, and has no `IF NOT EXISTS` for
+        // indexes (creating it again just fails)
+        let columns = columns
+            .iter()
+            .map(|c| format!("\"{c}\"(191)"))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        format!("CREATE UNIQUE INDEX \"{name}\" ON \"{table}\" ({columns})")
+    } else {
+        let columns = columns
+            .iter()
+            .map(|c| format!("\"{c}\""))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        format!("CREATE UNIQUE INDEX IF NOT EXISTS \"{name}\" ON \"{table}\" ({columns})")
+    }
+}
+
+// explore queries
+
+/// Build the query selecting a page of searchable posts
+///
+/// # Arguments
+/// * `db_type` - the type of the database
+/// * `config` - the config of the server (for table and column names)
+/// * `sort` - how to order the posts
+/// * `page` - the page number
+fn public_posts_query(db_type: &str, config: &Config, sort: PostSort, page: u32) -> String {
+    let limit = format!("LIMIT {POSTS_PER_PAGE} OFFSET {}", page_offset(page));
+    let posts = &config.table_posts;
+
+    match sort {
+        PostSort::Recent => format!(
+            "SELECT * FROM \"{}\" WHERE {} ORDER BY \"{}\" DESC {limit}",
+            posts.table_name,
+            searchable_condition(&format!("\"{}\"", posts.context)),
+            posts.date_published
+        ),
+        PostSort::Views => {
+            let counts = match config.view_mode {
+                ViewMode::OpenMultiple => format!(
+                    "SELECT \"slug\", CAST(\"count\" AS {}) AS \"count\" FROM \"{}\"",
+                    if db_type == "mysql" {
+                        "SIGNED"
+                    } else {
+                        "INTEGER"
+                    },
+                    config.table_view_counts.table_name
+                ),
+                ViewMode::AuthenticatedOnce => format!(
+                    "SELECT \"slug\", COUNT(*) AS \"count\" FROM \"{}\" GROUP BY \"slug\"",
+                    config.table_views.table_name
+                ),
+            };
+
+            format!(
+                "SELECT p.* FROM \"{}\" p LEFT JOIN ({counts}) v ON v.\"slug\" = p.\"{}\" WHERE {} ORDER BY COALESCE(v.\"count\", 0) DESC, p.\"{}\" DESC {limit}",
+                posts.table_name,
+                posts.slug,
+                searchable_condition(&format!("p.\"{}\"", posts.context)),
+                posts.date_published
+            )
+        }
+    }
+}
+
+/// Build the query selecting a page of the posts with the bound tag
+///
+/// # Arguments
+/// * `db_type` - the type of the database
+/// * `config` - the config of the server (for table and column names)
+/// * `page` - the page number
+fn posts_by_tag_query(db_type: &str, config: &Config, page: u32) -> String {
+    let param = if (db_type == "sqlite") | (db_type == "mysql") {
+        "?"
+    } else {
+        "$1"
+    };
+
+    let posts = &config.table_posts;
+    format!(
+        "SELECT p.* FROM \"{}\" p INNER JOIN \"{}\" t ON t.\"post\" = p.\"{}\" WHERE t.\"tag\" = {param} ORDER BY p.\"{}\" DESC LIMIT {POSTS_PER_PAGE} OFFSET {}",
+        posts.table_name,
+        config.table_tags.table_name,
+        posts.id,
+        posts.date_published,
+        page_offset(page)
+    )
+}
+
+/// Build the query suggesting the (at most 10) most used tags starting with the bound pattern
+///
+/// Only tags of searchable posts are counted, so suggestions never reveal anything
+/// about unlisted, private or password protected posts.
+///
+/// # Arguments
+/// * `db_type` - the type of the database
+/// * `config` - the config of the server (for table and column names)
+fn suggest_tags_query(db_type: &str, config: &Config) -> String {
+    let param = if (db_type == "sqlite") | (db_type == "mysql") {
+        "?"
+    } else {
+        "$1"
+    };
+
+    let posts = &config.table_posts;
+    format!(
+        "SELECT t.\"tag\", CAST(COUNT(*) AS {}) AS \"posts\" FROM \"{}\" t INNER JOIN \"{}\" p ON p.\"{}\" = t.\"post\" WHERE t.\"tag\" LIKE {param} AND {} GROUP BY t.\"tag\" ORDER BY COUNT(*) DESC, t.\"tag\" ASC LIMIT 10",
+        if db_type == "mysql" { "CHAR" } else { "TEXT" },
+        config.table_tags.table_name,
+        posts.table_name,
+        posts.id,
+        searchable_condition(&format!("p.\"{}\"", posts.context))
+    )
+}
+
+/// Build the condition matching the serialized contexts of searchable posts,
+/// see [`Post::is_searchable`]
+///
+/// Quotes in user given strings are escaped in JSON, so these patterns only match
+/// the fields themselves.
+///
+/// # Arguments
+/// * `context` - the (quoted) context column
+fn searchable_condition(context: &str) -> String {
+    [
+        "\"visibility\":\"Public\"",
+        "\"view_password\":\"\"",
+        "\"burn_after_read\":false",
+        "\"moderation\":\"Active\"",
+    ]
+    .iter()
+    .map(|field| format!("{context} LIKE '%{field}%'"))
+    .collect::<Vec<String>>()
+    .join(" AND ")
+}
+
+// expiry queries
+
+/// Build the query selecting the posts with an expiry, so every other post's context isn't
+/// parsed each sweep (the expiry itself is checked with [`PostContext::is_expired`])
+///
+/// Contexts are serialized without whitespace, so an unset expiry is always `"expires_at":0`
+/// followed by the next field or the end of the object, wherever the field is.
+///
+/// # Arguments
+/// * `config` - the config of the server (for table and column names)
+fn expiring_posts_query(config: &Config) -> String {
+    let posts = &config.table_posts;
+    let context = format!("\"{}\"", posts.context);
+
+    format!(
+        "SELECT \"{}\", \"{}\", {context} FROM \"{}\" WHERE {context} LIKE '%\"expires_at\":%' AND {context} NOT LIKE '%\"expires_at\":0,%' AND {context} NOT LIKE '%\"expires_at\":0}}%'",
+        posts.slug, posts.id, posts.table_name
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Count the bind parameters of a query, making sure it only uses one placeholder style
+    fn placeholders(query: &str) -> usize {
+        let question = query.matches('?').count();
+        let dollar = query.matches('$').count();
+
+        assert!(
+            (question == 0) | (dollar == 0),
+            "mixed placeholders in {query}"
+        );
+
+        // numbered placeholders must be `$1..$n`
+        for i in 1..=dollar {
+            assert!(query.contains(&format!("${i}")), "missing ${i} in {query}");
+        }
+
+        question + dollar
+    }
+
+    #[test]
+    fn password_failures_lock_and_expire() {
+        let now = 1_700_000_000_000;
+
+        // a missing counter is `incr`ed to 1, which is forgotten straight away
+        assert!(PasswordFailures::decode(1).is_stale(now));
+
+        let mut failures = PasswordFailures {
+            since: now,
+            failures: 4,
+        };
+
+        assert_eq!(
+            PasswordFailures::decode(failures.encode().parse().unwrap()),
+            failures
+        );
+        assert_eq!(failures.locked_until(), 0);
+
+        // `incr` adds one wrong password
+        failures = PasswordFailures::decode(failures.encode().parse::<u128>().unwrap() + 1);
+        assert_eq!(failures.failures, 5);
+        assert_eq!(failures.locked_until(), now + LOCK_DURATION);
+
+        failures.failures = 6;
+        assert_eq!(failures.locked_until(), now + LOCK_DURATION * 2);
+        assert!(!failures.is_stale(now + LOCK_DURATION));
+        assert!(failures.is_stale(now + FAILURE_WINDOW + 1));
+
+        failures.failures = 999;
+        assert_eq!(failures.locked_until(), now + MAX_LOCK_DURATION);
+    }
+
+    #[test]
+    fn editors_cant_rename_posts() {
+        assert!(!can_change_slug_or_password(
+            Some(AclRole::Editor),
+            false,
+            false
+        ));
+        assert!(!can_change_slug_or_password(None, true, false));
+
+        assert!(can_change_slug_or_password(
+            Some(AclRole::Admin),
+            false,
+            false
+        ));
+        assert!(can_change_slug_or_password(
+            Some(AclRole::Editor),
+            false,
+            true
+        ));
+        assert!(can_change_slug_or_password(None, false, false));
+    }
+
+    #[test]
+    fn view_password_blocks_clones() {
+        let post = Post {
+            id: String::from("id"),
+            slug: String::from("locked"),
+            content: String::from("secret"),
+            password: String::new(),
+            date_published: 0,
+            date_edited: 0,
+            context: PostContext {
+                view_password: String::from("hash"),
+                ..Default::default()
+            },
+            ips: Vec::new(),
+        };
+
+        // cloning copies the content but not the view password, so it needs the same check
+        assert!(needs_view_password(&post, true, false, false));
+        assert!(PostContext::from(post.clone()).view_password.is_empty());
+
+        assert!(!needs_view_password(&post, true, true, false));
+        assert!(!needs_view_password(&post, true, false, true));
+        assert!(!needs_view_password(&post, false, false, false));
+    }
+
+    #[test]
+    fn view_queries_sqlite() {
+        assert_eq!(
+            views_by_slug_query("sqlite", "views"),
+            "SELECT * FROM \"views\" WHERE \"slug\" = ?"
+        );
+        assert_eq!(placeholders(&view_by_user_query("sqlite", "views")), 2);
+        assert_eq!(placeholders(&create_view_query("sqlite", "views")), 2);
+    }
+
+    #[test]
+    fn view_queries_mysql() {
+        assert_eq!(placeholders(&views_by_slug_query("mysql", "views")), 1);
+        assert_eq!(placeholders(&view_by_user_query("mysql", "views")), 2);
+        assert_eq!(placeholders(&create_view_query("mysql", "views")), 2);
+    }
+
+    #[test]
+    fn view_queries_postgres() {
+        assert_eq!(
+            view_by_user_query("postgres", "views"),
+            "SELECT * FROM \"views\" WHERE \"slug\" = $1 AND \"id\" = $2"
+        );
+        assert_eq!(placeholders(&views_by_slug_query("postgres", "views")), 1);
+        assert_eq!(placeholders(&view_by_user_query("postgres", "views")), 2);
+        assert_eq!(placeholders(&create_view_query("postgres", "views")), 2);
+    }
+
+    #[test]
+    fn unique_index_queries() {
+        assert_eq!(
+            unique_index_query("sqlite", "views", &["slug", "id"]),
+            "CREATE UNIQUE INDEX IF NOT EXISTS \"views_slug_id_unique\" ON \"views\" (\"slug\", \"id\")"
+        );
+        assert_eq!(
+            unique_index_query("mysql", "aliases", &["slug"]),
+            "CREATE UNIQUE INDEX \"aliases_slug_unique\" ON \"aliases\" (\"slug\"(191))"
+        );
+    }
+
+    #[test]
+    fn increment_view_count_queries() {
+        assert_eq!(
+            increment_view_count_query("sqlite", "view_counts"),
+            "UPDATE \"view_counts\" SET \"count\" = CAST(CAST(\"count\" AS INTEGER) + CAST(? AS INTEGER) AS TEXT) WHERE \"slug\" = ?"
+        );
+        assert!(increment_view_count_query("mysql", "view_counts").contains("AS SIGNED"));
+
+        for db_type in ["sqlite", "mysql", "postgres"] {
+            assert_eq!(
+                placeholders(&increment_view_count_query(db_type, "view_counts")),
+                2
+            );
+        }
+    }
+
+    #[test]
+    fn public_posts_queries() {
+        let mut config = Config::default();
+
+        for view_mode in [ViewMode::OpenMultiple, ViewMode::AuthenticatedOnce] {
+            config.view_mode = view_mode;
+
+            for db_type in ["sqlite", "mysql", "postgres"] {
+                for sort in [PostSort::Recent, PostSort::Views] {
+                    let query = public_posts_query(db_type, &config, sort, 2);
+                    assert_eq!(placeholders(&query), 0);
+                    assert!(query.contains("\"visibility\":\"Public\""));
+                    assert!(query.ends_with(&format!(
+                        "LIMIT {POSTS_PER_PAGE} OFFSET {}",
+                        2 * POSTS_PER_PAGE
+                    )));
+                }
+            }
+        }
+
+        assert!(
+            public_posts_query("mysql", &Config::default(), PostSort::Views, 0)
+                .contains("AS SIGNED")
+        );
+    }
+
+    #[test]
+    fn suggest_tags_queries() {
+        let config = Config::default();
+
+        for db_type in ["sqlite", "mysql", "postgres"] {
+            let query = suggest_tags_query(db_type, &config);
+            assert_eq!(placeholders(&query), 1);
+            assert!(query.ends_with("LIMIT 10"));
+        }
+
+        assert!(suggest_tags_query("mysql", &config).contains("AS CHAR"));
+    }
+
+    #[test]
+    fn searchable_conditions() {
+        assert_eq!(
+            searchable_condition("\"context\""),
+            "\"context\" LIKE '%\"visibility\":\"Public\"%' AND \"context\" LIKE '%\"view_password\":\"\"%' AND \"context\" LIKE '%\"burn_after_read\":false%' AND \"context\" LIKE '%\"moderation\":\"Active\"%'"
+        );
+    }
+
+    #[test]
+    fn expiring_posts_ignore_field_order() {
+        let query = expiring_posts_query(&Config::default());
+        let patterns: Vec<&str> = query.split('\'').skip(1).step_by(2).collect();
+
+        // `LIKE '%...%'` patterns
+        let matches = |context: &str| {
+            context.contains(patterns[0].trim_matches('%'))
+                && !context.contains(patterns[1].trim_matches('%'))
+                && !context.contains(patterns[2].trim_matches('%'))
+        };
+
+        let mut context = PostContext::default();
+        assert!(!matches(&serde_json::to_string(&context).unwrap()));
+
+        context.expires_at = 1;
+        assert!(matches(&serde_json::to_string(&context).unwrap()));
+
+        // the field moved to the end, or a new field after it
+        assert!(!matches("{\"title\":\"\",\"expires_at\":0}"));
+        assert!(!matches("{\"expires_at\":0,\"new_field\":true}"));
+        assert!(matches("{\"title\":\"\",\"expires_at\":10}"));
+    }
+
+    #[test]
+    fn posts_by_tag_queries() {
+        let config = Config::default();
+
+        for db_type in ["sqlite", "mysql", "postgres"] {
+            assert_eq!(placeholders(&posts_by_tag_query(db_type, &config, 0)), 1);
+        }
+    }
 }
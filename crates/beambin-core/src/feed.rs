@@ -0,0 +1,188 @@
+//! Atom and JSON Feed syndication
+//!
+//! Feeds are built from posts whose content was already rendered to HTML, every link
+//! in them must be absolute.
+use serde_json::{json, Value};
+
+/// A post in a [`Feed`]
+#[derive(Debug, Clone)]
+pub struct FeedItem {
+    /// The absolute link to the post
+    pub url: String,
+    pub title: String,
+    pub summary: String,
+    /// The rendered content of the post
+    pub content_html: String,
+    /// The time (in milliseconds since the unix epoch) the post was published
+    pub published: u128,
+    /// The time (in milliseconds since the unix epoch) the post was last edited
+    pub updated: u128,
+}
+
+/// A list of posts syndicated as Atom or JSON Feed
+#[derive(Debug, Clone)]
+pub struct Feed {
+    pub title: String,
+    /// The absolute link to the page the feed follows
+    pub home_url: String,
+    /// The absolute link to the feed itself
+    pub feed_url: String,
+    pub items: Vec<FeedItem>,
+}
+
+impl Feed {
+    /// The time the feed last changed, the latest edit of its items
+    pub fn updated(&self) -> u128 {
+        self.items.iter().map(|i| i.updated).max().unwrap_or(0)
+    }
+
+    /// Render the feed as an Atom document
+    pub fn to_atom(&self) -> String {
+        let mut out = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<feed xmlns=\"http://www.w3.org/2005/Atom\">
+<title>{}</title>
+<id>{}</id>
+<link rel=\"self\" href=\"{}\" />
+<link rel=\"alternate\" href=\"{}\" />
+<updated>{}</updated>
+",
+            escape(&self.title),
+            escape(&self.feed_url),
+            escape(&self.feed_url),
+            escape(&self.home_url),
+            rfc3339(self.updated())
+        );
+
+        for item in &self.items {
+            out.push_str(&format!(
+                "<entry>
+<title>{}</title>
+<id>{}</id>
+<link rel=\"alternate\" href=\"{}\" />
+<published>{}</published>
+<updated>{}</updated>
+<summary>{}</summary>
+<content type=\"html\">{}</content>
+</entry>
+",
+                escape(&item.title),
+                escape(&item.url),
+                escape(&item.url),
+                rfc3339(item.published),
+                rfc3339(item.updated),
+                escape(&item.summary),
+                escape(&item.content_html)
+            ));
+        }
+
+        out.push_str("</feed>\n");
+        out
+    }
+
+    /// Render the feed as a JSON Feed (version 1.1) document
+    pub fn to_json(&self) -> Value {
+        json!({
+            "version": "https://jsonfeed.org/version/1.1",
+            "title": self.title,
+            "home_page_url": self.home_url,
+            "feed_url": self.feed_url,
+            "items": self
+                .items
+                .iter()
+                .map(|item| {
+                    json!({
+                        "id": item.url,
+                        "url": item.url,
+                        "title": item.title,
+                        "summary": item.summary,
+                        "content_html": item.content_html,
+                        "date_published": rfc3339(item.published),
+                        "date_modified": rfc3339(item.updated),
+                    })
+                })
+                .collect::<Vec<Value>>(),
+        })
+    }
+}
+
+/// Escape text for XML
+fn escape(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Format a time (in milliseconds since the unix epoch) as an RFC 3339 UTC date
+pub fn rfc3339(millis: u128) -> String {
+    let secs = (millis / 1000) as i64;
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+
+    // days to a civil date, see https://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed() -> Feed {
+        Feed {
+            title: "Runbooks & notes".to_string(),
+            home_url: "https://example.com/tag/runbook".to_string(),
+            feed_url: "https://example.com/tag/runbook/feed.xml".to_string(),
+            items: vec![FeedItem {
+                url: "https://example.com/restart-db".to_string(),
+                title: "Restarting <the> database".to_string(),
+                summary: String::new(),
+                content_html: "<p>Run \"restart\"</p>".to_string(),
+                published: 1_700_000_000_000,
+                updated: 1_700_000_060_000,
+            }],
+        }
+    }
+
+    #[test]
+    fn dates_are_rfc3339() {
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(951_782_400_000), "2000-02-29T00:00:00Z");
+        assert_eq!(rfc3339(1_700_000_000_000), "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn atom_is_escaped() {
+        let atom = feed().to_atom();
+
+        assert!(atom.contains("<title>Runbooks &amp; notes</title>"));
+        assert!(atom.contains("<title>Restarting &lt;the&gt; database</title>"));
+        assert!(atom.contains("&lt;p&gt;Run &quot;restart&quot;&lt;/p&gt;"));
+        assert!(atom.contains("<updated>2023-11-14T22:14:20Z</updated>"));
+    }
+
+    #[test]
+    fn json_feed_items() {
+        let json = feed().to_json();
+
+        assert_eq!(json["items"][0]["id"], "https://example.com/restart-db");
+        assert_eq!(json["items"][0]["content_html"], "<p>Run \"restart\"</p>");
+        assert_eq!(json["items"][0]["date_published"], "2023-11-14T22:13:20Z");
+    }
+}
@@ -2,9 +2,17 @@
 #![doc = include_str!("../../../README.md")]
 #![doc(issue_tracker_base_url = "https://github.com/trisuaso/beambin/issues")]
 pub mod api;
+pub mod ban;
+pub mod captcha;
 pub mod config;
 pub mod database;
+pub mod feed;
 pub mod model;
+pub mod password;
+pub mod ratelimit;
+pub mod search;
+pub mod stats;
+pub mod tags;
 
 pub use databeam::DatabaseOpts;
 
@@ -6,15 +6,15 @@ use axum::{
 
 use serde::{Deserialize, Serialize};
 use databeam::prelude::DefaultReturn;
+use databeam::utility;
+use std::collections::BTreeMap;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum ViewMode {
     /// Only authenticated users can count as a post view and only once
-    ///
-    /// Not implemented.
     AuthenticatedOnce,
     /// Anybody can count as a post view multiple times;
-    /// views are only stored in redis when using this mode
+    /// views are counted in the cache and periodically written to the database
     OpenMultiple,
 }
 
@@ -27,6 +27,31 @@ impl Default for ViewMode {
 /// (timestamp, IP)
 pub type IPLog = (u128, String);
 
+/// The role of a profile in the access control list of a [`Post`]
+///
+/// Every role includes the permissions of the roles before it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AclRole {
+    /// Can view the post without its view password
+    Viewer,
+    /// Can edit the post's content and context
+    Editor,
+    /// Can delete the post and manage its access control list
+    Admin,
+}
+
+/// Who can find and view a [`Post`]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Visibility {
+    /// Listed on the explore page and in search results
+    Public,
+    /// Only viewable by those who know the slug
+    #[default]
+    Unlisted,
+    /// Only viewable by the owner, profiles on the access list and share link holders
+    Private,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Post {
     pub id: String,
@@ -54,12 +79,21 @@ pub struct PostContext {
     /// Post favicon link
     #[serde(default)]
     pub favicon: String,
-    /// Post view password (can be disabled)
+    /// Hashed post view password (can be disabled)
+    ///
+    /// Only changed through [`EditContext::new_view_password`].
     #[serde(default)]
     pub view_password: String,
-    /// Post owner username
+    /// The ID of the profile which owns the post (blank if the post has no owner)
+    ///
+    /// Only changed by [`crate::database::Database::transfer_post`].
     #[serde(default)]
     pub owner: String,
+    /// The roles of profiles (by ID) other than the owner
+    ///
+    /// Only changed by [`crate::database::Database::set_post_role`].
+    #[serde(default)]
+    pub acl: BTreeMap<String, AclRole>,
     /// Post template settings
     ///
     /// * blank/no value = not a template and not using a template
@@ -68,11 +102,32 @@ pub struct PostContext {
     #[serde(default)]
     pub template: String,
     /// The slug of the next post in this collection
+    ///
+    /// Only shown if the post isn't part of any [`Collection`].
     #[serde(default)]
     pub next: String,
     /// The slug of the previous post in this collection
+    ///
+    /// Only shown if the post isn't part of any [`Collection`].
     #[serde(default)]
     pub previous: String,
+    /// The time (in milliseconds since the unix epoch) the post is deleted at, `0` if never
+    #[serde(default)]
+    pub expires_at: u128,
+    /// If the post is deleted the first time it is viewed
+    #[serde(default)]
+    pub burn_after_read: bool,
+    /// Who can find and view the post
+    #[serde(default)]
+    pub visibility: Visibility,
+    /// The (normalised) tags of the post
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The moderation state of the post
+    ///
+    /// Only changed by [`crate::database::Database::moderate_post`].
+    #[serde(default)]
+    pub moderation: Moderation,
 }
 
 impl Default for PostContext {
@@ -84,13 +139,72 @@ impl Default for PostContext {
             favicon: String::new(),
             view_password: String::new(),
             owner: String::new(),
+            acl: BTreeMap::new(),
             template: String::new(),
             next: String::new(),
             previous: String::new(),
+            expires_at: 0,
+            burn_after_read: false,
+            visibility: Visibility::default(),
+            tags: Vec::new(),
+            moderation: Moderation::default(),
         }
     }
 }
 
+impl PostContext {
+    /// Check if the post has passed its `expires_at` time
+    pub fn is_expired(&self) -> bool {
+        (self.expires_at != 0) && (self.expires_at <= rainbeam_shared::unix_epoch_timestamp())
+    }
+
+    /// Get the context without the fields only the post's editors should see
+    pub fn public(self) -> Self {
+        Self {
+            // never give out the (hashed) view password or who has access
+            view_password: String::new(),
+            acl: BTreeMap::new(),
+            ..self
+        }
+    }
+}
+
+impl Post {
+    /// Check if the post has passed its `expires_at` time
+    pub fn is_expired(&self) -> bool {
+        self.context.is_expired()
+    }
+
+    /// The name of the cookie holding the token which unlocks the post's view password
+    pub fn unlock_cookie(&self) -> String {
+        format!("__Secure-Unlock-{}", self.id)
+    }
+
+    /// Check if the post can be viewed, hidden or taken down posts can't
+    pub fn check_moderation(&self) -> Result<(), DatabaseError> {
+        match self.context.moderation {
+            Moderation::Active => Ok(()),
+            Moderation::Hidden => Err(DatabaseError::NotFound),
+            Moderation::TakenDown { legal: true, .. } => Err(DatabaseError::LegalTakedown),
+            Moderation::TakenDown { legal: false, .. } => Err(DatabaseError::TakenDown),
+        }
+    }
+
+    /// Check if the post can show up in search results and on the explore page
+    pub fn is_searchable(&self) -> bool {
+        (self.context.visibility == Visibility::Public)
+            && self.context.view_password.is_empty()
+            && !self.context.burn_after_read
+            && self.check_moderation().is_ok()
+    }
+
+    /// Check if the post was taken down, taken down posts can't be edited or deleted
+    /// (only moderated)
+    pub fn is_taken_down(&self) -> bool {
+        matches!(self.context.moderation, Moderation::TakenDown { .. })
+    }
+}
+
 impl From<Post> for PostContext {
     /// Convert the given post into [`PostContext`] which uses the post as a template
     fn from(value: Post) -> Self {
@@ -117,11 +231,317 @@ impl From<Post> for PublicPost {
             content: value.content,
             date_published: value.date_published,
             date_edited: value.date_edited,
-            context: value.context,
+            context: value.context.public(),
         }
     }
 }
 
+/// What a [`Share`] link allows its holder to do
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SharePermission {
+    /// View the post without its view password
+    View,
+    /// View the post and edit its content and context, but not its slug or passwords
+    Edit,
+}
+
+/// A signed link giving access to a [`Post`] without sharing its passwords
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Share {
+    pub id: String,
+    /// The `id` of the post this link belongs to
+    pub post: String,
+    pub permission: SharePermission,
+    /// The time the link was created
+    pub timestamp: u128,
+    /// The time the link stops working, `0` if it never expires
+    pub expires_at: u128,
+}
+
+impl Share {
+    /// Check if the link has expired
+    pub fn is_expired(&self) -> bool {
+        (self.expires_at != 0) && (utility::unix_epoch_timestamp() >= self.expires_at)
+    }
+}
+
+/// An ordered list of posts with an index page, owned by a profile
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Collection {
+    pub id: String,
+    pub title: String,
+    /// The ID of the profile which owns the collection
+    pub owner: String,
+    /// The slugs of the posts in the collection, in order
+    ///
+    /// A post is never in the same collection twice.
+    pub posts: Vec<String>,
+    /// The time the collection was created
+    pub date_created: u128,
+    /// The time the collection (or its posts) last changed
+    pub date_edited: u128,
+}
+
+/// The moderation state of a [`Post`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum Moderation {
+    /// The post can be viewed normally
+    #[default]
+    Active,
+    /// The post was hidden by a manager, only a tombstone is shown in its place
+    Hidden,
+    /// The post was removed by a manager, its content is gone but its slug stays
+    /// reserved and a tombstone explaining the removal is shown in its place
+    TakenDown {
+        /// Why the post was removed
+        reason: String,
+        /// If the post was removed for legal reasons (served as HTTP 451)
+        legal: bool,
+    },
+}
+
+/// A report of an abusive [`Post`], waiting to be reviewed by a manager
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Report {
+    pub id: String,
+    /// The `id` of the reported post
+    pub post: String,
+    /// Why the post was reported
+    pub reason: String,
+    /// The ID of the profile which sent the report, blank if they weren't logged in
+    pub reporter: String,
+    /// The IP the report was sent from
+    pub ip: String,
+    /// The time the report was sent
+    pub timestamp: u128,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateReport {
+    /// Why the post is being reported
+    pub reason: String,
+}
+
+/// Something a manager can do to a reported [`Post`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ModAction {
+    /// Replace the post with a tombstone
+    Hide,
+    /// Undo [`ModAction::Hide`] or [`ModAction::TakeDown`] (the content of taken down
+    /// posts is gone for good)
+    Restore,
+    /// Remove the post's content, leaving a tombstone which keeps its slug reserved
+    TakeDown,
+    /// Delete the post, freeing its slug
+    Delete,
+    /// Ban the IP the post was created from (its editors are left alone)
+    BanIp,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ModeratePost {
+    pub action: ModAction,
+    /// The reason given for takedowns and IP bans
+    #[serde(default)]
+    pub reason: String,
+    /// If a takedown is for legal reasons
+    #[serde(default)]
+    pub legal: bool,
+}
+
+/// A previous version of a [`Post`], stored every time the post is edited
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PostRevision {
+    pub id: String,
+    /// The `id` of the post this revision belongs to
+    pub post: String,
+    /// The time the edit which created this revision happened
+    pub timestamp: u128,
+    /// The ID of the profile which made the edit, or the hashed IP of the editor
+    /// if they weren't logged in
+    pub editor: String,
+    /// The content of the post before the edit
+    pub content: String,
+    /// The context of the post before the edit
+    pub context: PostContext,
+}
+
+/// A [`PostRevision`] without the fields only the post's editors should see
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublicRevision {
+    pub id: String,
+    /// The `id` of the post this revision belongs to
+    pub post: String,
+    /// The time the edit which created this revision happened
+    pub timestamp: u128,
+    /// The content of the post before the edit
+    pub content: String,
+    /// The context of the post before the edit
+    pub context: PostContext,
+}
+
+impl From<PostRevision> for PublicRevision {
+    fn from(value: PostRevision) -> Self {
+        Self {
+            id: value.id,
+            post: value.post,
+            timestamp: value.timestamp,
+            content: value.content,
+            context: value.context.public(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum DiffTag {
+    /// The line is the same in both versions
+    Equal,
+    /// The line only exists in the old version
+    Delete,
+    /// The line only exists in the new version
+    Insert,
+}
+
+/// A single line of a [`PostDiff`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiffLine {
+    pub tag: DiffTag,
+    /// The line number in the old version (if the line exists there)
+    pub old_line: Option<usize>,
+    /// The line number in the new version (if the line exists there)
+    pub new_line: Option<usize>,
+    pub content: String,
+}
+
+/// The changes between two versions of a [`Post`]'s content
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PostDiff {
+    /// The ID of the revision the diff starts from (blank for the current version)
+    pub from: String,
+    /// The ID of the revision the diff ends at (blank for the current version)
+    pub to: String,
+    /// The diff in unified format
+    pub unified: String,
+    /// Every line of both versions, in unified order
+    pub lines: Vec<DiffLine>,
+    /// Every line of both versions, paired as `(old, new)` for a side-by-side view
+    pub rows: Vec<(Option<DiffLine>, Option<DiffLine>)>,
+}
+
+impl PostDiff {
+    /// Diff `old` against `new`
+    ///
+    /// # Arguments
+    /// * `from` - the revision ID of `old`
+    /// * `to` - the revision ID of `new`
+    /// * `old` - the old content
+    /// * `new` - the new content
+    pub fn new(from: String, to: String, old: &str, new: &str) -> Self {
+        let diff = similar::TextDiff::from_lines(old, new);
+
+        let unified = diff
+            .unified_diff()
+            .context_radius(3)
+            .header(
+                if from.is_empty() { "current" } else { &from },
+                if to.is_empty() { "current" } else { &to },
+            )
+            .to_string();
+
+        // unified lines, and side-by-side rows built from the lines of each op
+        let mut lines = Vec::new();
+        let mut rows = Vec::new();
+
+        for op in diff.ops() {
+            let start = lines.len();
+
+            for change in diff.iter_changes(op) {
+                lines.push(DiffLine {
+                    tag: match change.tag() {
+                        similar::ChangeTag::Equal => DiffTag::Equal,
+                        similar::ChangeTag::Delete => DiffTag::Delete,
+                        similar::ChangeTag::Insert => DiffTag::Insert,
+                    },
+                    old_line: change.old_index().map(|i| i + 1),
+                    new_line: change.new_index().map(|i| i + 1),
+                    content: change.value().trim_end_matches(['\r', '\n']).to_string(),
+                })
+            }
+
+            let op_lines = &lines[start..];
+            let old_lines: Vec<&DiffLine> = op_lines
+                .iter()
+                .filter(|l| l.tag != DiffTag::Insert)
+                .collect();
+            let new_lines: Vec<&DiffLine> = op_lines
+                .iter()
+                .filter(|l| l.tag != DiffTag::Delete)
+                .collect();
+
+            for i in 0..old_lines.len().max(new_lines.len()) {
+                rows.push((
+                    old_lines.get(i).map(|l| (*l).clone()),
+                    new_lines.get(i).map(|l| (*l).clone()),
+                ))
+            }
+        }
+
+        Self {
+            from,
+            to,
+            unified,
+            lines,
+            rows,
+        }
+    }
+}
+
+/// The views of a [`Post`] on a single day
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PostDayStats {
+    /// The start of the day (in milliseconds since the unix epoch)
+    pub day: u128,
+    pub views: i32,
+    /// The approximate number of distinct visitors
+    pub unique_visitors: u64,
+}
+
+/// View analytics of a [`Post`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PostStats {
+    /// The total view count of the post
+    pub views: i32,
+    /// The approximate number of distinct visitors over every day in `days`
+    pub unique_visitors: u64,
+    /// Daily views, oldest first
+    pub days: Vec<PostDayStats>,
+}
+
+impl PostStats {
+    /// Get the `points` of an SVG polyline showing daily views
+    ///
+    /// # Arguments
+    /// * `width` - the width of the line
+    /// * `height` - the height of the line
+    pub fn sparkline(&self, width: f64, height: f64) -> String {
+        let max = self.days.iter().map(|d| d.views).max().unwrap_or(0).max(1) as f64;
+        let step = width / (self.days.len().max(2) - 1) as f64;
+
+        self.days
+            .iter()
+            .enumerate()
+            .map(|(i, d)| {
+                format!(
+                    "{:.1},{:.1}",
+                    i as f64 * step,
+                    height - (d.views as f64 / max * height)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
 // props
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -131,9 +551,22 @@ pub struct CreatePost {
     pub slug: String,
     /// The content of the post
     pub content: String,
-    /// The post edit password
+    /// The post edit password, a random password is used if this is blank
+    /// and the post has no owner
     #[serde(default)]
     pub password: String,
+    /// The time (in milliseconds since the unix epoch) the post is deleted at, `0` if never
+    #[serde(default)]
+    pub expires_at: u128,
+    /// If the post is deleted the first time it is viewed
+    #[serde(default)]
+    pub burn_after_read: bool,
+    /// Who can find and view the post
+    #[serde(default)]
+    pub visibility: Visibility,
+    /// The response to the captcha, if one is required
+    #[serde(default)]
+    pub captcha_response: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -143,20 +576,32 @@ pub struct ClonePost {
     /// The post's slug
     #[serde(default)]
     pub slug: String,
-    /// The post edit password
+    /// The post edit password, a random password is used if this is blank
+    /// and the post has no owner
     #[serde(default)]
     pub password: String,
+    /// The response to the captcha, if one is required
+    #[serde(default)]
+    pub captcha_response: String,
+    /// A share link token for the source post, if it can't be viewed otherwise
+    #[serde(default)]
+    pub share: String,
+    /// The token unlocking the source post's view password, taken from its unlock cookie
+    #[serde(skip)]
+    pub unlock: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DeletePost {
-    /// The password of the post
+    /// The password of the post (not needed by the owner)
+    #[serde(default)]
     pub password: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EditPost {
-    /// The password of the post
+    /// The password of the post (not needed by the owner)
+    #[serde(default)]
     pub password: String,
     /// The updated content of the post
     pub new_content: String,
@@ -166,14 +611,182 @@ pub struct EditPost {
     /// The updated slug of the post
     #[serde(default)]
     pub new_slug: String,
+    /// If the `next`/`previous` links of other posts should follow the new slug
+    #[serde(default)]
+    pub rewrite_links: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TransferPost {
+    /// The username or ID of the profile receiving the post
+    pub owner: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EditAcl {
+    /// The username or ID of the profile
+    pub profile: String,
+    /// The new role of the profile, `None` removes the profile from the list
+    #[serde(default)]
+    pub role: Option<AclRole>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateShare {
+    pub permission: SharePermission,
+    /// The number of hours the link works for, `0` if it never expires
+    #[serde(default)]
+    pub hours: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateCollection {
+    pub title: String,
+    /// The slugs of the posts the collection starts with
+    #[serde(default)]
+    pub posts: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EditCollection {
+    pub title: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InsertCollectionPost {
+    /// The slug of the post
+    pub slug: String,
+    /// Where the post goes, `None` to add it at the end
+    #[serde(default)]
+    pub index: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RemoveCollectionPost {
+    /// The slug of the post
+    pub slug: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReorderCollection {
+    /// Every slug of the collection, in their new order
+    pub posts: Vec<String>,
+}
+
+/// A share link token given to a read endpoint
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ShareQuery {
+    #[serde(default)]
+    pub share: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EditContext {
-    /// The password of the post
+    /// The password of the post (not needed by the owner)
+    #[serde(default)]
     pub password: String,
     /// The updated metadata of the post
     pub context: PostContext,
+    /// The new view password of the post, `None` to keep it and an empty string to remove it
+    #[serde(default)]
+    pub new_view_password: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DiffQuery {
+    /// The ID of the revision to diff from (blank for the current version)
+    #[serde(default)]
+    pub from: String,
+    /// The ID of the revision to diff to (blank for the current version)
+    #[serde(default)]
+    pub to: String,
+}
+
+/// How to order the posts listed on the explore page
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PostSort {
+    /// Newest first
+    #[default]
+    Recent,
+    /// Most viewed first
+    Views,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExploreQuery {
+    /// How to order the posts
+    #[serde(default)]
+    pub sort: PostSort,
+    /// The page number (starting at 0)
+    #[serde(default)]
+    pub page: u32,
+}
+
+/// A tag and the number of posts using it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TagCount {
+    pub tag: String,
+    pub posts: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TagQuery {
+    /// The start of the tags to suggest
+    #[serde(default)]
+    pub q: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SearchQuery {
+    /// The words to search for
+    #[serde(default)]
+    pub q: String,
+    /// The page number (starting at 0)
+    #[serde(default)]
+    pub page: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PageQuery {
+    /// The page number (starting at 0)
+    #[serde(default)]
+    pub page: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BulkPosts {
+    /// The slugs of the posts to act on
+    pub slugs: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TransferPosts {
+    /// The slugs of the posts to transfer
+    pub slugs: Vec<String>,
+    /// The username or ID of the profile receiving the posts
+    pub owner: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StatsQuery {
+    /// The number of days to include
+    #[serde(default = "default_stats_days")]
+    pub days: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StatsRequest {
+    /// The password of the post (not needed by its owner, admins or managers)
+    #[serde(default)]
+    pub password: String,
+    /// The number of days to include
+    #[serde(default = "default_stats_days")]
+    pub days: u32,
+}
+
+fn default_stats_days() -> u32 {
+    30
 }
 
 /// General API errors
@@ -183,10 +796,16 @@ pub enum DatabaseError {
     ContentTooShort,
     ContentTooLong,
     AlreadyExists,
+    MustBeUnique,
     NotAllowed,
     ValueError,
     NotFound,
     Banned,
+    TooManyAttempts,
+    RateLimited,
+    CaptchaFailed,
+    TakenDown,
+    LegalTakedown,
     Other,
 }
 
@@ -198,12 +817,20 @@ impl DatabaseError {
             ContentTooShort => String::from("Content too short!"),
             ContentTooLong => String::from("Content too long!"),
             AlreadyExists => String::from("A post with this slug already exists."),
+            MustBeUnique => String::from("This already exists, it may have just been created."),
             NotAllowed => String::from("You are not allowed to do this!"),
             ValueError => String::from("One of the field values given is invalid!"),
             NotFound => {
                 String::from("Nothing with this path exists or you do not have access to it!")
             }
             Banned => String::from("You're banned for suspected systems abuse or violating TOS."),
+            TooManyAttempts => {
+                String::from("Too many wrong passwords were given. Please try again later.")
+            }
+            RateLimited => String::from("You're doing this too often. Please slow down."),
+            CaptchaFailed => String::from("Please solve the captcha."),
+            TakenDown => String::from("This post has been taken down by a moderator."),
+            LegalTakedown => String::from("This post has been taken down for legal reasons."),
             _ => String::from("An unspecified error has occured"),
         }
     }
@@ -222,7 +849,7 @@ impl IntoResponse for DatabaseError {
                 }),
             )
                 .into_response(),
-            AlreadyExists => (
+            AlreadyExists | MustBeUnique | CaptchaFailed => (
                 StatusCode::BAD_REQUEST,
                 Json(DefaultReturn::<u16> {
                     success: false,
@@ -240,6 +867,42 @@ impl IntoResponse for DatabaseError {
                 }),
             )
                 .into_response(),
+            Banned => (
+                StatusCode::FORBIDDEN,
+                Json(DefaultReturn::<u16> {
+                    success: false,
+                    message: self.to_string(),
+                    payload: 403,
+                }),
+            )
+                .into_response(),
+            TakenDown => (
+                StatusCode::GONE,
+                Json(DefaultReturn::<u16> {
+                    success: false,
+                    message: self.to_string(),
+                    payload: 410,
+                }),
+            )
+                .into_response(),
+            LegalTakedown => (
+                StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
+                Json(DefaultReturn::<u16> {
+                    success: false,
+                    message: self.to_string(),
+                    payload: 451,
+                }),
+            )
+                .into_response(),
+            TooManyAttempts | RateLimited => (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(DefaultReturn::<u16> {
+                    success: false,
+                    message: self.to_string(),
+                    payload: 429,
+                }),
+            )
+                .into_response(),
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(DefaultReturn::<u16> {
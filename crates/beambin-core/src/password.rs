@@ -0,0 +1,105 @@
+//! Post password hashing
+//!
+//! Passwords are hashed with Argon2id and stored as PHC strings. Posts created before
+//! that have an unsalted SHA-256 hash, which still verifies but should be replaced
+//! (see [`needs_rehash`]).
+use argon2::password_hash::{
+    rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+};
+use argon2::Argon2;
+use databeam::utility;
+use subtle::ConstantTimeEq;
+
+/// Hash a password into a PHC string
+///
+/// # Arguments
+/// * `password` - the password
+pub fn hash(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("failed to hash password")
+        .to_string()
+}
+
+/// Check a password against a stored hash in constant time
+///
+/// An empty stored hash never matches.
+///
+/// # Arguments
+/// * `password` - the password
+/// * `stored` - the output of [`hash`] (or an old SHA-256 hash)
+pub fn verify(password: &str, stored: &str) -> bool {
+    if is_legacy(stored) {
+        return utility::hash(password.to_string())
+            .as_bytes()
+            .ct_eq(stored.as_bytes())
+            .into();
+    }
+
+    match PasswordHash::new(stored) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Check if a stored hash should be replaced with one from [`hash`] the next time
+/// its password is given to us
+///
+/// # Arguments
+/// * `stored` - the stored hash
+pub fn needs_rehash(stored: &str) -> bool {
+    is_legacy(stored)
+}
+
+/// Check if a stored password is a hash at all (and not a plain text view password from
+/// before view passwords were hashed)
+///
+/// # Arguments
+/// * `stored` - the stored password
+pub fn is_hash(stored: &str) -> bool {
+    is_legacy(stored) | PasswordHash::new(stored).is_ok()
+}
+
+/// Check if a stored hash is an unsalted SHA-256 hash from [`utility::hash`]
+fn is_legacy(stored: &str) -> bool {
+    (stored.len() == 64) && stored.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_and_verify() {
+        let hashed = hash("correct horse");
+
+        assert!(hashed.starts_with("$argon2id$"));
+        assert!(verify("correct horse", &hashed));
+        assert!(!verify("battery staple", &hashed));
+        assert!(!needs_rehash(&hashed));
+
+        // salted
+        assert_ne!(hashed, hash("correct horse"));
+    }
+
+    #[test]
+    fn legacy_hashes_verify() {
+        let hashed = utility::hash("correct horse".to_string());
+
+        assert!(verify("correct horse", &hashed));
+        assert!(!verify("battery staple", &hashed));
+        assert!(needs_rehash(&hashed));
+        assert!(is_hash(&hashed));
+    }
+
+    #[test]
+    fn empty_never_matches() {
+        assert!(!verify("", ""));
+        assert!(!verify("anything", ""));
+        assert!(!is_hash("plain text"));
+    }
+}
@@ -0,0 +1,271 @@
+//! Token bucket rate limiting
+use crate::config::{Config, RateLimit};
+use crate::database::Database;
+use crate::model::DatabaseError;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum_extra::extract::CookieJar;
+use databeam::utility;
+use std::net::SocketAddr;
+
+/// A group of routes sharing one budget
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitGroup {
+    /// Creating and cloning posts
+    Posts,
+    /// Rendering markdown previews
+    Render,
+    /// Reporting posts
+    Reports,
+}
+
+impl RateLimitGroup {
+    /// The name of the group, used in cache keys
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Posts => "posts",
+            Self::Render => "render",
+            Self::Reports => "reports",
+        }
+    }
+
+    /// The budget of the group
+    ///
+    /// # Arguments
+    /// * `config` - the server config
+    pub fn limit<'a>(&self, config: &'a Config) -> &'a RateLimit {
+        match self {
+            Self::Posts => &config.rate_limits.posts,
+            Self::Render => &config.rate_limits.render,
+            Self::Reports => &config.rate_limits.reports,
+        }
+    }
+}
+
+/// The result of taking a token from a bucket
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitStatus {
+    /// The capacity of the bucket
+    pub limit: u32,
+    /// The number of tokens left in the bucket
+    pub remaining: u32,
+    /// The number of seconds until the bucket is full again
+    pub reset: u64,
+    /// The number of seconds until a token is available, `None` if one was taken
+    pub retry_after: Option<u64>,
+}
+
+impl RateLimitStatus {
+    /// Add the `RateLimit-*` (and `Retry-After`) headers to a response
+    ///
+    /// # Arguments
+    /// * `headers` - the headers of the response
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        headers.insert("RateLimit-Limit", HeaderValue::from(self.limit));
+        headers.insert("RateLimit-Remaining", HeaderValue::from(self.remaining));
+        headers.insert("RateLimit-Reset", HeaderValue::from(self.reset));
+
+        if let Some(retry_after) = self.retry_after {
+            headers.insert("Retry-After", HeaderValue::from(retry_after));
+        }
+    }
+}
+
+/// Get the time (in milliseconds) it takes a bucket to get a token back
+///
+/// # Arguments
+/// * `limit` - the budget of the bucket
+pub fn emission_interval(limit: &RateLimit) -> u128 {
+    (60_000 / u128::from(limit.per_minute.max(1))).max(1)
+}
+
+/// Take a token from a bucket
+///
+/// A bucket is stored as the time it will be full again, counted in
+/// [`emission_interval`]s. Taking a token adds one interval to it, so the cache can do
+/// that atomically with `incr` before this is called.
+///
+/// # Arguments
+/// * `limit` - the budget of the bucket
+/// * `full_at` - the stored time the bucket is full again, after adding one to it
+/// * `now` - the current time
+///
+/// # Returns
+/// * `(status, store)`, `store` is the value to store instead if the bucket had been full
+///   or no token was taken
+pub fn take_token(limit: &RateLimit, full_at: u128, now: u128) -> (RateLimitStatus, Option<u128>) {
+    let interval = emission_interval(limit);
+    let capacity = u128::from(limit.capacity);
+    let current = now / interval;
+
+    let mut full_at = full_at;
+    let mut store = None;
+
+    if full_at <= current {
+        // the bucket was full (or new), its refill can't be saved up
+        // (rounded down to a whole interval, so this token can come back up to one early)
+        full_at = current + 1;
+        store = Some(full_at);
+    }
+
+    let retry_after = if full_at > current + capacity {
+        // empty, give the token back
+        full_at = current + capacity;
+        store = Some(full_at);
+
+        Some(
+            (((current + 1) * interval - now) as f64 / 1000.0)
+                .ceil()
+                .max(1.0) as u64,
+        )
+    } else {
+        None
+    };
+
+    (
+        RateLimitStatus {
+            limit: limit.capacity,
+            remaining: (current + capacity - full_at) as u32,
+            reset: ((full_at * interval).saturating_sub(now) as f64 / 1000.0).ceil() as u64,
+            retry_after,
+        },
+        store,
+    )
+}
+
+/// Get the key a request is counted under: its profile, or its IP
+///
+/// # Arguments
+/// * `database` - the database
+/// * `req` - the request
+async fn request_key(database: &Database, req: &Request) -> String {
+    if let Some(c) = CookieJar::from_headers(req.headers()).get("__Secure-Token") {
+        if let Ok(ua) = database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+        {
+            return format!("profile:{}", ua.id);
+        }
+    }
+
+    let real_ip = database.real_ip(
+        req.headers(),
+        req.extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr),
+    );
+
+    format!("ip:{}", utility::hash(real_ip))
+}
+
+/// Count a request, or reject it once its budget is spent
+///
+/// # Arguments
+/// * `database` - the database
+/// * `group` - the route group of the request
+/// * `req` - the request
+/// * `next` - the rest of the middleware stack
+async fn limit(database: Database, group: RateLimitGroup, req: Request, next: Next) -> Response {
+    if group.limit(&database.config).capacity == 0 {
+        return next.run(req).await;
+    }
+
+    let key = request_key(&database, &req).await;
+    let status = database.take_rate_limit_token(group, &key).await;
+
+    let mut res = if status.retry_after.is_some() {
+        DatabaseError::RateLimited.into_response()
+    } else {
+        next.run(req).await
+    };
+
+    status.apply(res.headers_mut());
+    res
+}
+
+/// Rate limit middleware for [`RateLimitGroup::Posts`]
+pub async fn limit_posts(State(database): State<Database>, req: Request, next: Next) -> Response {
+    limit(database, RateLimitGroup::Posts, req, next).await
+}
+
+/// Rate limit middleware for [`RateLimitGroup::Render`]
+pub async fn limit_render(State(database): State<Database>, req: Request, next: Next) -> Response {
+    limit(database, RateLimitGroup::Render, req, next).await
+}
+
+/// Rate limit middleware for [`RateLimitGroup::Reports`]
+pub async fn limit_reports(State(database): State<Database>, req: Request, next: Next) -> Response {
+    limit(database, RateLimitGroup::Reports, req, next).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: RateLimit = RateLimit {
+        capacity: 2,
+        per_minute: 6,
+    };
+
+    /// Take a token the way [`Database::take_rate_limit_token`] does
+    fn take(stored: &mut u128, now: u128) -> RateLimitStatus {
+        *stored += 1;
+
+        let (status, store) = take_token(&LIMIT, *stored, now);
+
+        if let Some(store) = store {
+            *stored = store;
+        }
+
+        status
+    }
+
+    #[test]
+    fn bucket_empties_then_refills() {
+        let mut stored = 0;
+        let now = 1_000_000;
+
+        assert_eq!(take(&mut stored, now).retry_after, None);
+        assert_eq!(take(&mut stored, now).retry_after, None);
+
+        // empty, a token comes back every 10 seconds
+        let status = take(&mut stored, now);
+        assert_eq!(status.remaining, 0);
+        assert_eq!(status.retry_after, Some(10));
+        assert_eq!(status.reset, 20);
+
+        // refused requests don't push the refill back
+        assert_eq!(take(&mut stored, now + 5000).retry_after, Some(5));
+        assert_eq!(take(&mut stored, now + 10_000).retry_after, None);
+        assert!(take(&mut stored, now + 10_000).retry_after.is_some());
+    }
+
+    #[test]
+    fn bucket_never_overfills() {
+        let mut stored = 0;
+        let now = 1_000_000;
+        take(&mut stored, now);
+
+        let status = take(&mut stored, now + 60 * 60 * 1000);
+        assert_eq!(status.remaining, 1);
+        assert_eq!(status.reset, 10);
+    }
+
+    #[test]
+    fn bucket_allows_no_burst_across_boundaries() {
+        let mut stored = 0;
+        let now = 1_009_999;
+
+        assert_eq!(take(&mut stored, now).retry_after, None);
+        assert_eq!(take(&mut stored, now).retry_after, None);
+
+        // a fixed window would allow 2 more here, the bucket at most one token early
+        assert_eq!(take(&mut stored, now + 1).retry_after, None);
+        assert!(take(&mut stored, now + 1).retry_after.is_some());
+        assert!(take(&mut stored, now + 2).retry_after.is_some());
+    }
+}
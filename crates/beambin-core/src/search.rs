@@ -0,0 +1,143 @@
+//! Full-text search queries
+//!
+//! Searchable posts are copied into a separate index table: an FTS5 table with SQLite,
+//! a `tsvector` column with Postgres and a `FULLTEXT` index with MySQL.
+use crate::database::{page_offset, POSTS_PER_PAGE};
+
+/// Build the queries creating the search index
+///
+/// # Arguments
+/// * `db_type` - the type of the database
+/// * `table` - the name of the search table
+pub fn create_index_queries(db_type: &str, table: &str) -> Vec<String> {
+    match db_type {
+        "sqlite" => vec![format!(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS \"{table}\" USING fts5(
+                id UNINDEXED,
+                slug UNINDEXED,
+                title,
+                description,
+                content
+            )"
+        )],
+        "mysql" => vec![format!(
+            "CREATE TABLE IF NOT EXISTS \"{table}\" (
+                id          TEXT,
+                slug        TEXT,
+                title       TEXT,
+                description TEXT,
+                content     LONGTEXT,
+                FULLTEXT (title, description, content)
+            )"
+        )],
+        _ => vec![
+            format!(
+                "CREATE TABLE IF NOT EXISTS \"{table}\" (
+                    id          TEXT,
+                    slug        TEXT,
+                    title       TEXT,
+                    description TEXT,
+                    content     TEXT,
+                    document    TSVECTOR GENERATED ALWAYS AS (
+                        to_tsvector('simple', title || ' ' || description || ' ' || content)
+                    ) STORED
+                )"
+            ),
+            format!(
+                "CREATE INDEX IF NOT EXISTS \"{table}_document\" ON \"{table}\" USING GIN (document)"
+            ),
+        ],
+    }
+}
+
+/// Build the query adding a post to the search index
+///
+/// # Arguments
+/// * `db_type` - the type of the database
+/// * `table` - the name of the search table
+pub fn insert_query(db_type: &str, table: &str) -> String {
+    if (db_type == "sqlite") | (db_type == "mysql") {
+        format!("INSERT INTO \"{table}\" (id, slug, title, description, content) VALUES (?, ?, ?, ?, ?)")
+    } else {
+        format!("INSERT INTO \"{table}\" (id, slug, title, description, content) VALUES ($1, $2, $3, $4, $5)")
+    }
+}
+
+/// Build the query removing a post from the search index
+///
+/// # Arguments
+/// * `db_type` - the type of the database
+/// * `table` - the name of the search table
+pub fn delete_query(db_type: &str, table: &str) -> String {
+    if (db_type == "sqlite") | (db_type == "mysql") {
+        format!("DELETE FROM \"{table}\" WHERE id = ?")
+    } else {
+        format!("DELETE FROM \"{table}\" WHERE id = $1")
+    }
+}
+
+/// Build the query selecting the slugs of the posts matching a search, best match first
+///
+/// The search itself is bound as the only parameter, see [`search_param`].
+///
+/// # Arguments
+/// * `db_type` - the type of the database
+/// * `table` - the name of the search table
+/// * `page` - the page number
+pub fn search_query(db_type: &str, table: &str, page: u32) -> String {
+    let limit = format!("LIMIT {POSTS_PER_PAGE} OFFSET {}", page_offset(page));
+
+    match db_type {
+        "sqlite" => format!(
+            "SELECT slug FROM \"{table}\" WHERE \"{table}\" MATCH ? ORDER BY rank {limit}"
+        ),
+        "mysql" => format!(
+            "SELECT slug FROM \"{table}\" WHERE MATCH (title, description, content) AGAINST (? IN NATURAL LANGUAGE MODE) {limit}"
+        ),
+        _ => format!(
+            "SELECT slug FROM \"{table}\" WHERE document @@ plainto_tsquery('simple', $1) ORDER BY ts_rank(document, plainto_tsquery('simple', $1)) DESC {limit}"
+        ),
+    }
+}
+
+/// Turn what a user typed into the parameter of [`search_query`]
+///
+/// FTS5 has its own query syntax, so every word is quoted to be matched literally
+/// (and all of them must match). The other databases take plain text.
+///
+/// # Arguments
+/// * `db_type` - the type of the database
+/// * `q` - the search
+pub fn search_param(db_type: &str, q: &str) -> String {
+    if db_type != "sqlite" {
+        return q.trim().to_string();
+    }
+
+    q.split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fts5_words_are_quoted() {
+        assert_eq!(
+            search_param("sqlite", "  hello  world "),
+            "\"hello\" \"world\""
+        );
+        assert_eq!(
+            search_param("sqlite", "NOT say \"hi\" OR*"),
+            "\"NOT\" \"say\" \"\"\"hi\"\"\" \"OR*\""
+        );
+        assert_eq!(search_param("sqlite", "   "), "");
+    }
+
+    #[test]
+    fn plain_text_elsewhere() {
+        assert_eq!(search_param("postgres", " hello world "), "hello world");
+    }
+}
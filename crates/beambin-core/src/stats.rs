@@ -0,0 +1,117 @@
+//! Post view analytics helpers
+
+/// The number of bits of a hash used to pick a register
+const PRECISION: u32 = 10;
+/// The number of registers in a [`HyperLogLog`]
+const REGISTERS: usize = 1 << PRECISION;
+
+/// User agent fragments of crawlers and link preview bots, these shouldn't count as views
+const CRAWLERS: &[&str] = &[
+    "bot",
+    "crawl",
+    "spider",
+    "slurp",
+    "facebookexternalhit",
+    "embedly",
+    "mediapartners",
+    "lighthouse",
+    "headlesschrome",
+    "python-requests",
+    "go-http-client",
+];
+
+/// Check if a user agent belongs to a known crawler
+///
+/// # Arguments
+/// * `user_agent` - the value of the `User-Agent` header
+pub fn is_crawler(user_agent: &str) -> bool {
+    let user_agent = user_agent.to_lowercase();
+    CRAWLERS.iter().any(|c| user_agent.contains(c))
+}
+
+/// Approximate distinct counter
+///
+/// Stored as a hex string of its registers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HyperLogLog {
+    /// Create a new, empty [`HyperLogLog`]
+    pub fn new() -> Self {
+        Self {
+            registers: vec![0; REGISTERS],
+        }
+    }
+
+    /// Read a [`HyperLogLog`] from its hex representation, invalid input gives an empty counter
+    ///
+    /// # Arguments
+    /// * `hex` - the output of [`HyperLogLog::to_hex`]
+    pub fn from_hex(hex: &str) -> Self {
+        let registers: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .filter_map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect();
+
+        if registers.len() != REGISTERS {
+            return Self::new();
+        }
+
+        Self { registers }
+    }
+
+    /// Get the hex representation of the counter
+    pub fn to_hex(&self) -> String {
+        self.registers.iter().map(|r| format!("{r:02x}")).collect()
+    }
+
+    /// Add an item to the counter
+    ///
+    /// # Arguments
+    /// * `hash` - a hex encoded hash of the item (at least 16 characters)
+    pub fn insert(&mut self, hash: &str) {
+        let hash = match hash.get(0..16).map(|h| u64::from_str_radix(h, 16)) {
+            Some(Ok(h)) => h,
+            _ => return,
+        };
+
+        let index = (hash >> (64 - PRECISION)) as usize;
+        let rank = ((hash << PRECISION).leading_zeros() + 1).min(64 - PRECISION + 1) as u8;
+
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    /// Add every item of another counter to this counter
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        for (register, other) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    /// Estimate the number of distinct items added to the counter
+    pub fn count(&self) -> u64 {
+        let m = REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+
+        let sum: f64 = self.registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+        let estimate = alpha * m * m / sum;
+
+        // use linear counting for small numbers
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        if (estimate <= 2.5 * m) && (zeros > 0) {
+            return (m * (m / zeros as f64).ln()).round() as u64;
+        }
+
+        estimate.round() as u64
+    }
+}
@@ -0,0 +1,108 @@
+//! Post tag normalisation
+//!
+//! Tags are stored (and looked up) in one normal form so `Runbook`, `runbook ` and
+//! `RUNBOOK` are all the same tag.
+use crate::model::DatabaseError;
+
+/// The most tags a post can have
+pub const MAX_TAGS: usize = 16;
+
+/// The longest a (normalised) tag can be, in characters
+pub const MAX_TAG_LENGTH: usize = 32;
+
+/// Normalise a single tag
+///
+/// Tags are lowercase, with runs of whitespace, `_` and `-` turned into a single `-`.
+/// Any other character that isn't alphanumeric is dropped.
+///
+/// # Returns
+/// * `None` if nothing is left of the tag
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let mut out = String::new();
+
+    for c in tag.trim().to_lowercase().chars() {
+        if c.is_alphanumeric() {
+            out.push(c);
+        } else if (c.is_whitespace() | (c == '_') | (c == '-'))
+            && !out.is_empty()
+            && !out.ends_with('-')
+        {
+            out.push('-');
+        }
+    }
+
+    while out.ends_with('-') {
+        out.pop();
+    }
+
+    if out.is_empty() {
+        None
+    } else {
+        Some(out)
+    }
+}
+
+/// Normalise the tags of a post, dropping empty tags and duplicates
+///
+/// # Returns
+/// * `DatabaseError::ValueError` if there are too many tags or one of them is too long
+pub fn normalize_tags(tags: Vec<String>) -> Result<Vec<String>, DatabaseError> {
+    let mut out: Vec<String> = Vec::new();
+
+    for tag in tags {
+        let Some(tag) = normalize_tag(&tag) else {
+            continue;
+        };
+
+        if tag.chars().count() > MAX_TAG_LENGTH {
+            return Err(DatabaseError::ValueError);
+        }
+
+        if !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+
+    if out.len() > MAX_TAGS {
+        return Err(DatabaseError::ValueError);
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_normalised() {
+        assert_eq!(normalize_tag("  Runbook "), Some("runbook".to_string()));
+        assert_eq!(
+            normalize_tag("On Call__Rota"),
+            Some("on-call-rota".to_string())
+        );
+        assert_eq!(normalize_tag("c++ & rust!"), Some("c-rust".to_string()));
+        assert_eq!(normalize_tag("-- ?? --"), None);
+    }
+
+    #[test]
+    fn duplicates_are_dropped() {
+        assert_eq!(
+            normalize_tags(vec![
+                "Runbook".to_string(),
+                "runbook".to_string(),
+                "".to_string(),
+                "db".to_string(),
+            ])
+            .unwrap(),
+            vec!["runbook".to_string(), "db".to_string()]
+        );
+    }
+
+    #[test]
+    fn limits_are_enforced() {
+        assert!(normalize_tags(vec!["a".repeat(MAX_TAG_LENGTH + 1)]).is_err());
+        assert!(normalize_tags((0..=MAX_TAGS).map(|i| i.to_string()).collect()).is_err());
+        assert!(normalize_tags((0..MAX_TAGS).map(|i| i.to_string()).collect()).is_ok());
+    }
+}
@@ -0,0 +1,219 @@
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+
+use beambin_core::{
+    database::Database,
+    feed::{Feed, FeedItem},
+    model::{DatabaseError, Post, PostSort},
+};
+use rainbeam_shared::ui::render_markdown as md;
+
+pub fn routes(database: Database) -> Router {
+    Router::new()
+        .route("/feed.xml", get(site_atom))
+        .route("/feed.json", get(site_json))
+        .route("/c/{collection}/feed.xml", get(collection_atom))
+        .route("/c/{collection}/feed.json", get(collection_json))
+        .route("/tag/{tag}/feed.xml", get(tag_atom))
+        .route("/tag/{tag}/feed.json", get(tag_json))
+        // `/@{owner}/feed.xml`, post slugs can't contain `@`
+        .route("/{slug}/feed.xml", get(owner_atom))
+        .route("/{slug}/feed.json", get(owner_json))
+        .with_state(database)
+}
+
+/// The format a feed is served in
+#[derive(Clone, Copy)]
+enum Format {
+    Atom,
+    Json,
+}
+
+impl Format {
+    /// The extension of the feed's file name
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Atom => "xml",
+            Format::Json => "json",
+        }
+    }
+
+    /// Serve a feed in this format
+    fn respond(&self, feed: Feed) -> Response {
+        match self {
+            Format::Atom => (
+                [(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+                feed.to_atom(),
+            )
+                .into_response(),
+            Format::Json => (
+                [(header::CONTENT_TYPE, "application/feed+json; charset=utf-8")],
+                feed.to_json().to_string(),
+            )
+                .into_response(),
+        }
+    }
+}
+
+/// Build a feed of posts
+///
+/// # Arguments
+/// * `database` - the database
+/// * `format` - the format the feed is served in
+/// * `title` - the title of the feed
+/// * `home` - the path of the page the feed follows
+/// * `base` - the path the feed is served under
+/// * `posts` - the posts in the feed, posts which can't be viewed without a password
+///   (or only once) are skipped
+fn build_feed(
+    database: &Database,
+    format: Format,
+    title: String,
+    home: String,
+    base: String,
+    posts: Vec<Post>,
+) -> Feed {
+    // links in feeds are read outside of this site, so they can't be relative
+    let host = database.config.host.trim_end_matches('/');
+
+    Feed {
+        title,
+        home_url: format!("{host}{home}"),
+        feed_url: format!("{host}{base}/feed.{}", format.extension()),
+        items: posts
+            .into_iter()
+            .filter(|p| {
+                p.context.view_password.is_empty() && !p.context.burn_after_read && !p.is_expired()
+            })
+            .map(|p| FeedItem {
+                url: format!("{host}/{}", p.slug),
+                title: if p.context.title.is_empty() {
+                    p.slug.clone()
+                } else {
+                    p.context.title.clone()
+                },
+                summary: p.context.description.clone(),
+                content_html: md(&p.content),
+                published: p.date_published,
+                updated: p.date_edited,
+            })
+            .collect(),
+    }
+}
+
+/// The newest public posts (`/feed.xml`)
+async fn site_feed(database: Database, format: Format) -> Response {
+    match database.get_public_posts(PostSort::Recent, 0).await {
+        Ok((posts, _)) => format.respond(build_feed(
+            &database,
+            format,
+            database.config.name.clone(),
+            "/".to_string(),
+            String::new(),
+            posts,
+        )),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn site_atom(State(database): State<Database>) -> Response {
+    site_feed(database, Format::Atom).await
+}
+
+async fn site_json(State(database): State<Database>) -> Response {
+    site_feed(database, Format::Json).await
+}
+
+/// The posts of a collection, in order (`/c/{collection}/feed.xml`)
+async fn collection_feed(database: Database, id: String, format: Format) -> Response {
+    let collection = match database.get_collection(id).await {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+
+    let posts = database.get_collection_posts(&collection, None).await;
+    format.respond(build_feed(
+        &database,
+        format,
+        collection.title.clone(),
+        format!("/c/{}", collection.id),
+        format!("/c/{}", collection.id),
+        posts,
+    ))
+}
+
+async fn collection_atom(State(database): State<Database>, Path(id): Path<String>) -> Response {
+    collection_feed(database, id, Format::Atom).await
+}
+
+async fn collection_json(State(database): State<Database>, Path(id): Path<String>) -> Response {
+    collection_feed(database, id, Format::Json).await
+}
+
+/// The newest public posts with a tag (`/tag/{tag}/feed.xml`)
+async fn tag_feed(database: Database, tag: String, format: Format) -> Response {
+    let tag = match beambin_core::tags::normalize_tag(&tag) {
+        Some(t) => t,
+        None => return DatabaseError::NotFound.into_response(),
+    };
+
+    match database.get_posts_by_tag(tag.clone(), 0, None).await {
+        Ok((posts, _)) => format.respond(build_feed(
+            &database,
+            format,
+            format!("#{tag} – {}", database.config.name),
+            format!("/tag/{tag}"),
+            format!("/tag/{tag}"),
+            posts,
+        )),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn tag_atom(State(database): State<Database>, Path(tag): Path<String>) -> Response {
+    tag_feed(database, tag, Format::Atom).await
+}
+
+async fn tag_json(State(database): State<Database>, Path(tag): Path<String>) -> Response {
+    tag_feed(database, tag, Format::Json).await
+}
+
+/// The newest public posts owned by a profile (`/@{owner}/feed.xml`)
+async fn owner_feed(database: Database, slug: String, format: Format) -> Response {
+    let owner = match slug.strip_prefix('@') {
+        Some(o) => o.to_string(),
+        None => return DatabaseError::NotFound.into_response(),
+    };
+
+    let profile = match database.auth.get_profile(owner).await {
+        Ok(p) => p,
+        Err(_) => return DatabaseError::NotFound.into_response(),
+    };
+
+    match database.get_posts_by_owner(profile.id.clone(), 0).await {
+        Ok((posts, _)) => format.respond(build_feed(
+            &database,
+            format,
+            format!("{} – {}", profile.username, database.config.name),
+            // owners don't have a page of their own
+            "/".to_string(),
+            format!("/@{}", profile.username),
+            // only public posts, owners never chose to list their unlisted ones
+            posts.into_iter().filter(|p| p.is_searchable()).collect(),
+        )),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn owner_atom(State(database): State<Database>, Path(slug): Path<String>) -> Response {
+    owner_feed(database, slug, Format::Atom).await
+}
+
+async fn owner_json(State(database): State<Database>, Path(slug): Path<String>) -> Response {
+    owner_feed(database, slug, Format::Json).await
+}
@@ -2,10 +2,12 @@ use axum::routing::get_service;
 use axum::Router;
 
 use databeam::config::Config as DataConf;
+use std::net::SocketAddr;
 
 use tower_http::trace::{self, TraceLayer};
 use tracing::{info, Level};
 
+mod feeds;
 mod pages;
 pub use beambin_core::database;
 pub use beambin_core::model;
@@ -23,16 +25,16 @@ static GLOBAL: MiMalloc = MiMalloc;
 // ...
 #[tokio::main]
 async fn main() {
-    let mut config = config::Config::get_config();
-
-    let static_dir = pathbufd::PathBufD::current().extend(&[".config", "static"]);
-    config.static_dir = static_dir.clone();
-
     tracing_subscriber::fmt()
         .with_target(false)
         .compact()
         .init();
 
+    let mut config = config::Config::get_config();
+
+    let static_dir = pathbufd::PathBufD::current().extend(&[".config", "static"]);
+    config.static_dir = static_dir.clone();
+
     // init database
     let auth_database = beambin_core::AuthDatabase::new(
         DataConf::get_config().connection, // pull connection config from config file
@@ -59,10 +61,45 @@ async fn main() {
     .await;
     database.init().await;
 
+    // purge expired posts
+    let sweeper_database = database.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+
+        loop {
+            interval.tick().await;
+
+            let purged = sweeper_database.purge_expired_posts().await;
+            if purged > 0 {
+                info!("Purged {purged} expired posts");
+            }
+        }
+    });
+
+    // write view counts and stats to the database
+    let flusher_database = database.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+
+        loop {
+            interval.tick().await;
+            flusher_database.flush_views().await;
+            flusher_database.flush_stats().await;
+        }
+    });
+
     // ...
     let app = Router::new()
         .merge(pages::routes(database.clone()))
+        .merge(feeds::routes(database.clone()))
         .nest("/api/v1/posts", api::posts::routes(database.clone()))
+        .nest("/api/v1/me", api::me::routes(database.clone()))
+        .nest("/api/v1/reports", api::reports::routes(database.clone()))
+        .nest("/api/v1/tags", api::tags::routes(database.clone()))
+        .nest(
+            "/api/v1/collections",
+            api::collections::routes(database.clone()),
+        )
         .nest("/api/v0/util", api::util::routes(database.clone()))
         .nest("/api/v0/auth", beambin_core::authapi::routes(auth_database))
         .nest_service(
@@ -81,5 +118,42 @@ async fn main() {
         .unwrap();
 
     info!("🐝 Starting server at: http://localhost:{}!", config.port);
-    axum::serve(listener, app).await.unwrap();
+    // the socket address is the IP of requests without `real_ip_header`
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .unwrap();
+
+    // don't lose views counted since the last flush
+    let flushed = database.flush_views().await;
+    info!("Flushed view counts of {flushed} posts");
+
+    let flushed = database.flush_stats().await;
+    info!("Flushed {flushed} days of post stats");
+}
+
+/// Wait for ctrl+c or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.unwrap();
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .unwrap()
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
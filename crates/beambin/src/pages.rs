@@ -1,31 +1,61 @@
 use askama_axum::Template;
 use axum::{
-    extract::{Path, State, Query},
-    response::{Html, Json, IntoResponse},
+    extract::{ConnectInfo, Form, Path, State, Query},
+    http::{header, HeaderMap, StatusCode},
+    middleware,
+    response::{Html, Json, IntoResponse, Redirect, Response},
     routing::{get, post},
     Router,
 };
 
-use axum_extra::extract::CookieJar;
+use axum_extra::extract::{
+    cookie::{Cookie, SameSite},
+    CookieJar,
+};
 use serde::{Serialize, Deserialize};
+use std::net::SocketAddr;
 
 use beambin_core::{
     auth::Profile,
+    ban::check_bans,
+    captcha::needs_captcha,
     config::Config,
     database::Database,
-    model::{DatabaseError, Post},
+    model::{
+        AclRole, Collection, DatabaseError, DiffTag, Share, SharePermission, Post, PostContext,
+        PostDiff, PostRevision, PostStats, PostSort, Moderation, Report, ExploreQuery, SearchQuery,
+        ViewMode,
+    },
+    ratelimit::limit_render,
+    stats::is_crawler,
+    tags::normalize_tag,
 };
 use rainbeam_shared::ui::render_markdown as md;
 
 pub fn routes(database: Database) -> Router {
     Router::new()
         .route("/", get(homepage))
+        .route("/dashboard", get(dashboard_request))
+        .route("/mod", get(mod_request))
+        .route("/search", get(search_request))
+        .route("/explore", get(explore_request))
+        .route("/tag/{tag}", get(tag_request))
+        .route("/c/{collection}", get(collection_request))
         // post
         .route("/{slug}/edit/config", get(config_editor_request))
         .route("/{slug}/edit", get(editor_request))
+        .route("/{slug}/diff", get(diff_request))
+        .route("/{slug}/unlock", post(unlock_request))
         .route("/{slug}", get(view_post_request))
         // ...
-        .route("/api/v0/render", post(render_markdown))
+        .route(
+            "/api/v0/render",
+            post(render_markdown).route_layer(middleware::from_fn_with_state(
+                database.clone(),
+                limit_render,
+            )),
+        )
+        .route_layer(middleware::from_fn_with_state(database.clone(), check_bans))
         .with_state(database)
 }
 
@@ -33,12 +63,412 @@ pub fn routes(database: Database) -> Router {
 #[template(path = "homepage.html")]
 struct HomepageTemplate {
     config: Config,
+    /// If a captcha must be solved to create a post
+    needs_captcha: bool,
+    /// The rendered content of the information post, if it's shown
+    info_post: Option<String>,
+    /// The most recent public posts, if they're shown
+    recent_posts: Vec<Post>,
 }
 
-pub async fn homepage(State(database): State<Database>) -> impl IntoResponse {
+pub async fn homepage(jar: CookieJar, State(database): State<Database>) -> impl IntoResponse {
+    // get auth token
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+            .ok(),
+        None => None,
+    };
+
+    // only show the information post to everyone if anyone could view it
+    let info_post = if database.config.homepage_info_post {
+        match database
+            .get_post(database.config.info_post_slug.clone())
+            .await
+        {
+            Ok(p) if p.is_searchable() => Some(md(&p.content)),
+            _ => None,
+        }
+    } else {
+        None
+    };
+
+    let recent_posts = if database.config.homepage_recent_posts {
+        database
+            .get_public_posts(PostSort::Recent, 0)
+            .await
+            .map(|(posts, _)| posts)
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
     Html(
         HomepageTemplate {
+            needs_captcha: needs_captcha(&database.config, auth_user.as_deref()),
+            info_post,
+            recent_posts,
+            config: database.config,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "explore.html")]
+struct ExploreTemplate {
+    config: Config,
+    /// `(post, views)`
+    posts: Vec<(Post, i32)>,
+    sort: PostSort,
+    page: u32,
+    has_next: bool,
+}
+
+pub async fn explore_request(
+    State(database): State<Database>,
+    Query(query_params): Query<ExploreQuery>,
+) -> impl IntoResponse {
+    let (posts, has_next) = match database
+        .get_public_posts(query_params.sort, query_params.page)
+        .await
+    {
+        Ok(p) => p,
+        Err(e) => {
+            return Html(
+                ErrorViewTemplate {
+                    config: database.config,
+                    error: e.to_string(),
+                }
+                .render()
+                .unwrap(),
+            )
+        }
+    };
+
+    let mut with_views = Vec::new();
+    for post in posts {
+        let views = database.get_views_by_slug(post.slug.clone()).await;
+        with_views.push((post, views));
+    }
+
+    Html(
+        ExploreTemplate {
+            config: database.config,
+            posts: with_views,
+            sort: query_params.sort,
+            page: query_params.page,
+            has_next,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "dashboard.html")]
+struct DashboardTemplate {
+    config: Config,
+    profile: Box<Profile>,
+    /// `(post, views)`
+    posts: Vec<(Post, i32)>,
+    page: u32,
+    has_next: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DashboardQuery {
+    #[serde(default)]
+    page: u32,
+}
+
+pub async fn dashboard_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Query(query_params): Query<DashboardQuery>,
+) -> impl IntoResponse {
+    // get auth token
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+            .ok(),
+        None => None,
+    };
+
+    let auth_user = match auth_user {
+        Some(ua) => ua,
+        None => {
+            return Html(
+                ErrorViewTemplate {
+                    config: database.config,
+                    error: DatabaseError::NotAllowed.to_string(),
+                }
+                .render()
+                .unwrap(),
+            )
+        }
+    };
+
+    // get posts
+    let (posts, has_next) = match database
+        .get_posts_by_owner(auth_user.id.clone(), query_params.page)
+        .await
+    {
+        Ok(p) => p,
+        Err(e) => {
+            return Html(
+                ErrorViewTemplate {
+                    config: database.config,
+                    error: e.to_string(),
+                }
+                .render()
+                .unwrap(),
+            )
+        }
+    };
+
+    let mut with_views = Vec::new();
+    for post in posts {
+        let views = database.get_views_by_slug(post.slug.clone()).await;
+        with_views.push((post, views));
+    }
+
+    Html(
+        DashboardTemplate {
+            config: database.config,
+            profile: auth_user,
+            posts: with_views,
+            page: query_params.page,
+            has_next,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "mod.html")]
+struct ModTemplate {
+    config: Config,
+    /// `(report, post)`
+    reports: Vec<(Report, Post)>,
+    page: u32,
+    has_next: bool,
+}
+
+pub async fn mod_request(
+    jar: CookieJar,
+    State(database): State<Database>,
+    Query(query_params): Query<DashboardQuery>,
+) -> impl IntoResponse {
+    // get auth token
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+            .ok(),
+        None => None,
+    };
+
+    let auth_user = match auth_user {
+        Some(ua) => ua,
+        None => {
+            return Html(
+                ErrorViewTemplate {
+                    config: database.config,
+                    error: DatabaseError::NotAllowed.to_string(),
+                }
+                .render()
+                .unwrap(),
+            )
+        }
+    };
+
+    // get reports
+    let (reports, has_next) = match database.get_reports(query_params.page, auth_user).await {
+        Ok(r) => r,
+        Err(e) => {
+            return Html(
+                ErrorViewTemplate {
+                    config: database.config,
+                    error: e.to_string(),
+                }
+                .render()
+                .unwrap(),
+            )
+        }
+    };
+
+    Html(
+        ModTemplate {
+            config: database.config,
+            reports,
+            page: query_params.page,
+            has_next,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "search.html")]
+struct SearchTemplate {
+    config: Config,
+    q: String,
+    posts: Vec<Post>,
+    page: u32,
+    has_next: bool,
+}
+
+pub async fn search_request(
+    State(database): State<Database>,
+    Query(query_params): Query<SearchQuery>,
+) -> impl IntoResponse {
+    let (posts, has_next) = match database
+        .search_posts(query_params.q.clone(), query_params.page)
+        .await
+    {
+        Ok(p) => p,
+        Err(e) => {
+            return Html(
+                ErrorViewTemplate {
+                    config: database.config,
+                    error: e.to_string(),
+                }
+                .render()
+                .unwrap(),
+            )
+        }
+    };
+
+    Html(
+        SearchTemplate {
+            config: database.config,
+            q: query_params.q,
+            posts,
+            page: query_params.page,
+            has_next,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "tag.html")]
+struct TagTemplate {
+    config: Config,
+    tag: String,
+    posts: Vec<Post>,
+    page: u32,
+    has_next: bool,
+}
+
+pub async fn tag_request(
+    jar: CookieJar,
+    Path(tag): Path<String>,
+    State(database): State<Database>,
+    Query(query_params): Query<DashboardQuery>,
+) -> impl IntoResponse {
+    // get auth token
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+            .ok(),
+        None => None,
+    };
+
+    let (posts, has_next) = match database
+        .get_posts_by_tag(tag.clone(), query_params.page, auth_user.as_deref())
+        .await
+    {
+        Ok(p) => p,
+        Err(e) => {
+            return Html(
+                ErrorViewTemplate {
+                    config: database.config,
+                    error: e.to_string(),
+                }
+                .render()
+                .unwrap(),
+            )
+        }
+    };
+
+    Html(
+        TagTemplate {
             config: database.config,
+            tag: normalize_tag(&tag).unwrap_or_default(),
+            posts,
+            page: query_params.page,
+            has_next,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "collection.html")]
+struct CollectionTemplate {
+    config: Config,
+    collection: Collection,
+    posts: Vec<Post>,
+    owner: Option<Box<Profile>>,
+}
+
+pub async fn collection_request(
+    jar: CookieJar,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    // get auth token
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+            .ok(),
+        None => None,
+    };
+
+    let collection = match database.get_collection(id).await {
+        Ok(c) => c,
+        Err(e) => {
+            return Html(
+                ErrorViewTemplate {
+                    config: database.config,
+                    error: e.to_string(),
+                }
+                .render()
+                .unwrap(),
+            )
+        }
+    };
+
+    let posts = database
+        .get_collection_posts(&collection, auth_user.as_deref())
+        .await;
+
+    Html(
+        CollectionTemplate {
+            config: database.config.clone(),
+            owner: database
+                .auth
+                .get_profile(collection.owner.clone())
+                .await
+                .ok(),
+            collection,
+            posts,
         }
         .render()
         .unwrap(),
@@ -50,7 +480,15 @@ pub async fn homepage(State(database): State<Database>) -> impl IntoResponse {
 struct PostViewTemplate {
     config: Config,
     post: Post,
+    /// `(collection, previous, next)` of every collection the post is in
+    collections: Vec<(Collection, Option<Post>, Option<Post>)>,
+    revision: Option<PostRevision>,
+    stats: Option<PostStats>,
     owner: Option<Box<Profile>>,
+    /// The token of the edit share link the post was opened with
+    edit_share: String,
+    /// If a captcha must be solved to use the post as a template
+    needs_captcha: bool,
     rendered: String,
     title: String,
     views: i32,
@@ -59,8 +497,9 @@ struct PostViewTemplate {
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct PostViewQuery {
+    /// A share link token
     #[serde(default)]
-    view_password: String,
+    share: String,
 }
 
 #[derive(Template)]
@@ -68,6 +507,108 @@ pub struct PostViewQuery {
 struct PostPasswordTemplate {
     config: Config,
     post: Post,
+    /// The page to go back to once the post is unlocked
+    next: String,
+}
+
+/// Check if a post's view password was entered recently (or the post doesn't need one)
+fn is_unlocked(database: &Database, jar: &CookieJar, post: &Post) -> bool {
+    if !database.config.view_password | post.context.view_password.is_empty() {
+        return true;
+    }
+
+    match jar.get(&post.unlock_cookie()) {
+        Some(c) => database.check_unlock(post, c.value_trimmed()),
+        None => false,
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UnlockForm {
+    view_password: String,
+    /// The page to go back to once the post is unlocked
+    #[serde(default)]
+    next: String,
+}
+
+/// Unlock a post's view password (`/{slug}/unlock`)
+pub async fn unlock_request(
+    jar: CookieJar,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(slug): Path<String>,
+    State(database): State<Database>,
+    Form(props): Form<UnlockForm>,
+) -> Response {
+    // only go back to pages on this site
+    let next = local_path(&props.next)
+        .map(String::from)
+        .unwrap_or_else(|| format!("/{slug}"));
+
+    // get real ip
+    let real_ip = database.real_ip(&headers, Some(&addr));
+
+    match database
+        .unlock_post_view(slug, props.view_password, &real_ip)
+        .await
+    {
+        Ok((post, token)) => {
+            // the token itself expires, so the cookie can live for the session
+            let cookie = Cookie::build((post.unlock_cookie(), token))
+                .path("/")
+                .secure(true)
+                .http_only(true)
+                .same_site(SameSite::Lax);
+
+            (jar.add(cookie), Redirect::to(&next)).into_response()
+        }
+        Err(e) => Redirect::to(&with_query(
+            &next,
+            &format!("ANNC={}&ANNC_TYPE=error", encode_query(&e.to_string())),
+        ))
+        .into_response(),
+    }
+}
+
+/// Get `next` if it is a path on this site (no scheme, no authority)
+fn local_path(next: &str) -> Option<&str> {
+    // browsers treat `\` like `/`, so `/\evil.com` is as bad as `//evil.com`
+    if !next.starts_with('/')
+        || next.starts_with("//")
+        || next.contains('\\')
+        || next.chars().any(|c| c.is_control())
+    {
+        return None;
+    }
+
+    Some(next)
+}
+
+/// Add `query` to the query string of `path`, keeping any fragment last
+fn with_query(path: &str, query: &str) -> String {
+    let (path, fragment) = match path.split_once('#') {
+        Some((path, fragment)) => (path, format!("#{fragment}")),
+        None => (path, String::new()),
+    };
+
+    let separator = if path.contains('?') { '&' } else { '?' };
+    format!("{path}{separator}{query}{fragment}")
+}
+
+/// Percent-encode everything but unreserved characters
+fn encode_query(value: &str) -> String {
+    let mut out = String::new();
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    out
 }
 
 #[derive(Template)]
@@ -77,65 +618,235 @@ struct ErrorViewTemplate {
     error: String,
 }
 
+#[derive(Template)]
+#[template(path = "post/tombstone.html")]
+struct TombstoneTemplate {
+    config: Config,
+    post: Post,
+}
+
+/// Render the tombstone shown in place of a moderated post
+fn tombstone(config: Config, post: Post) -> Response {
+    let status = match post.check_moderation() {
+        Err(DatabaseError::LegalTakedown) => StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
+        Err(DatabaseError::TakenDown) => StatusCode::GONE,
+        _ => StatusCode::NOT_FOUND,
+    };
+
+    (
+        status,
+        Html(TombstoneTemplate { config, post }.render().unwrap()),
+    )
+        .into_response()
+}
+
 pub async fn view_post_request(
+    jar: CookieJar,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Path(slug): Path<String>,
     State(database): State<Database>,
     Query(query_params): Query<PostViewQuery>,
-) -> impl IntoResponse {
-    match database.get_post(slug).await {
-        Ok(p) => {
-            // check for view password
-            if database.config.view_password == true {
-                match query_params.view_password.is_empty() {
-                    false => {
-                        if !p.context.view_password.is_empty()
-                            && (query_params.view_password != p.context.view_password)
-                        {
-                            return Html(
-                                PostPasswordTemplate {
-                                    config: database.config,
-                                    post: p,
-                                }
-                                .render()
-                                .unwrap(),
-                            );
+) -> Response {
+    // get auth token
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+            .ok(),
+        None => None,
+    };
+
+    // get real ip
+    let real_ip = database.real_ip(&headers, Some(&addr));
+
+    // crawlers don't count as views
+    let is_crawler = is_crawler(
+        headers
+            .get(header::USER_AGENT)
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or(""),
+    );
+
+    // `{slug}@{revision}` views an older revision of the post
+    let (slug, revision) = match slug.rsplit_once('@') {
+        Some((slug, revision)) => (slug.to_string(), Some(revision.to_string())),
+        None => (slug, None),
+    };
+
+    match database.get_post(slug.clone()).await {
+        Ok(mut p) => {
+            // moderated posts are replaced by a tombstone
+            if p.check_moderation().is_err() {
+                return tombstone(database.config, p);
+            }
+
+            let unlock = jar.get(&p.unlock_cookie()).map(|c| c.value_trimmed());
+
+            let share = match database
+                .can_view(&p, auth_user.as_deref(), &query_params.share, unlock)
+                .await
+            {
+                Ok(share) => share,
+                // check for view password
+                Err(DatabaseError::PasswordIncorrect) => {
+                    return Html(
+                        PostPasswordTemplate {
+                            config: database.config,
+                            next: format!("/{}", p.slug),
+                            post: p,
                         }
-                    }
-                    true => {
-                        if !p.context.view_password.is_empty() {
-                            return Html(
-                                PostPasswordTemplate {
-                                    config: database.config,
-                                    post: p,
-                                }
-                                .render()
-                                .unwrap(),
-                            );
+                        .render()
+                        .unwrap(),
+                    )
+                    .into_response()
+                }
+                Err(e) => {
+                    return Html(
+                        ErrorViewTemplate {
+                            config: database.config,
+                            error: e.to_string(),
                         }
-                    }
+                        .render()
+                        .unwrap(),
+                    )
+                    .into_response()
+                }
+            };
+
+            // burn after read posts are removed before they're rendered,
+            // so only one request can ever see them
+            if p.context.burn_after_read {
+                let burned = match revision {
+                    Some(_) => Err(DatabaseError::NotFound),
+                    // link previews would burn the post before its reader opens it,
+                    // so crawlers get nothing and the post stays
+                    None if is_crawler => Err(DatabaseError::NotAllowed),
+                    None => database.remove_post(&p).await,
+                };
+
+                if let Err(e) = burned {
+                    return Html(
+                        ErrorViewTemplate {
+                            config: database.config,
+                            error: e.to_string(),
+                        }
+                        .render()
+                        .unwrap(),
+                    )
+                    .into_response();
                 }
             }
 
+            // get revision
+            let revision = match revision {
+                Some(id) => match database.get_revision(id).await {
+                    Ok(r) if r.post == p.id => {
+                        // only how the post looked is taken from the revision, who can
+                        // view it always comes from the current post
+                        p.content = r.content.clone();
+                        p.context.title = r.context.title.clone();
+                        p.context.description = r.context.description.clone();
+                        p.context.theme_color = r.context.theme_color.clone();
+                        p.context.favicon = r.context.favicon.clone();
+                        Some(r)
+                    }
+                    _ => {
+                        return Html(
+                            ErrorViewTemplate {
+                                config: database.config,
+                                error: DatabaseError::NotFound.to_string(),
+                            }
+                            .render()
+                            .unwrap(),
+                        )
+                        .into_response()
+                    }
+                },
+                None => None,
+            };
+
             // push view
             // we could not support paste views by just.. not doing this
-            if let Err(e) = database.incr_views_by_slug(p.slug.clone()).await {
-                return Html(
-                    ErrorViewTemplate {
-                        config: database.config,
-                        error: e.to_string(),
+            if revision.is_none() && !p.context.burn_after_read && !is_crawler {
+                database.create_stats_view(p.slug.clone(), real_ip);
+
+                let res = match database.config.view_mode {
+                    ViewMode::OpenMultiple => database.incr_views_by_slug(p.slug.clone()).await,
+                    ViewMode::AuthenticatedOnce => match auth_user {
+                        Some(ref ua) => {
+                            database
+                                .create_view_by_user(p.slug.clone(), ua.id.clone())
+                                .await
+                        }
+                        None => Ok(()),
+                    },
+                };
+
+                match res {
+                    // (the view was already logged by another request)
+                    Ok(_) | Err(DatabaseError::MustBeUnique) => (),
+                    Err(e) => {
+                        return Html(
+                            ErrorViewTemplate {
+                                config: database.config,
+                                error: e.to_string(),
+                            }
+                            .render()
+                            .unwrap(),
+                        )
+                        .into_response()
                     }
-                    .render()
-                    .unwrap(),
-                );
+                }
+            }
+
+            // navigation between the posts of its collections, skipping the posts
+            // this user can't view
+            let mut collections = Vec::new();
+            for collection in database
+                .get_collections_by_post(p.slug.clone())
+                .await
+                .unwrap_or_default()
+            {
+                let posts = database
+                    .get_collection_posts(&collection, auth_user.as_deref())
+                    .await;
+
+                let (previous, next) = match posts.iter().position(|c| c.slug == p.slug) {
+                    Some(i) => (
+                        if i > 0 {
+                            posts.get(i - 1).cloned()
+                        } else {
+                            None
+                        },
+                        posts.get(i + 1).cloned(),
+                    ),
+                    None => (None, None),
+                };
+
+                collections.push((collection, previous, next));
             }
 
+            // owners, admins and managers get to see stats
+            let stats = match database
+                .can_view_stats(&p, auth_user.as_deref(), "", "")
+                .await
+            {
+                Ok(_) => database.get_post_stats(p.slug.clone(), 30).await.ok(),
+                Err(_) => None,
+            };
+
             // ...
             let rendered = md(&p.content.clone());
             Html(
                 PostViewTemplate {
                     config: database.config.clone(),
                     post: p.clone(),
-                    owner: if !p.context.owner.is_empty() {
+                    collections,
+                    revision,
+                    stats,
+                    owner: if database.config.post_ownership && !p.context.owner.is_empty() {
                         if let Ok(profile) =
                             database.auth.get_profile(p.context.owner.clone()).await
                         {
@@ -146,6 +857,11 @@ pub async fn view_post_request(
                     } else {
                         None
                     },
+                    edit_share: match share {
+                        Some(SharePermission::Edit) => query_params.share,
+                        _ => String::new(),
+                    },
+                    needs_captcha: needs_captcha(&database.config, auth_user.as_deref()),
                     rendered,
                     title: match p.context.title.is_empty() {
                         true => p.slug.clone(),
@@ -181,6 +897,164 @@ pub async fn view_post_request(
                 .render()
                 .unwrap(),
             )
+            .into_response()
+        }
+        Err(DatabaseError::NotFound) => match database.get_alias(slug).await {
+            // the post was renamed, send the old link to the new slug
+            Ok(target) => (
+                StatusCode::MOVED_PERMANENTLY,
+                [(
+                    header::LOCATION,
+                    match revision {
+                        Some(id) => format!("/{target}@{id}"),
+                        None => format!("/{target}"),
+                    },
+                )],
+            )
+                .into_response(),
+            Err(e) => Html(
+                ErrorViewTemplate {
+                    config: database.config,
+                    error: e.to_string(),
+                }
+                .render()
+                .unwrap(),
+            )
+            .into_response(),
+        },
+        Err(e) => Html(
+            ErrorViewTemplate {
+                config: database.config,
+                error: e.to_string(),
+            }
+            .render()
+            .unwrap(),
+        )
+        .into_response(),
+    }
+}
+
+#[derive(Template)]
+#[template(path = "post/diff.html")]
+struct DiffTemplate {
+    config: Config,
+    post: Post,
+    diff: PostDiff,
+    split: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DiffViewQuery {
+    #[serde(default)]
+    from: String,
+    #[serde(default)]
+    to: String,
+    /// `split` for a side-by-side view, anything else for a unified view
+    #[serde(default)]
+    mode: String,
+    #[serde(default)]
+    share: String,
+}
+
+pub async fn diff_request(
+    jar: CookieJar,
+    Path(slug): Path<String>,
+    State(database): State<Database>,
+    Query(query_params): Query<DiffViewQuery>,
+) -> impl IntoResponse {
+    // get auth token
+    let auth_user = match jar.get("__Secure-Token") {
+        Some(c) => database
+            .auth
+            .get_profile_by_unhashed(c.value_trimmed().to_string())
+            .await
+            .ok(),
+        None => None,
+    };
+
+    match database.get_post(slug).await {
+        Ok(p) => {
+            if let Err(e) = p.check_moderation() {
+                return Html(
+                    ErrorViewTemplate {
+                        config: database.config,
+                        error: e.to_string(),
+                    }
+                    .render()
+                    .unwrap(),
+                );
+            }
+
+            let unlock = jar.get(&p.unlock_cookie()).map(|c| c.value_trimmed());
+
+            match database
+                .can_view(&p, auth_user.as_deref(), &query_params.share, unlock)
+                .await
+            {
+                Ok(_) => (),
+                // check for view password
+                Err(DatabaseError::PasswordIncorrect) => {
+                    return Html(
+                        PostPasswordTemplate {
+                            config: database.config,
+                            next: format!("/{}/diff", p.slug),
+                            post: p,
+                        }
+                        .render()
+                        .unwrap(),
+                    )
+                }
+                Err(e) => {
+                    return Html(
+                        ErrorViewTemplate {
+                            config: database.config,
+                            error: e.to_string(),
+                        }
+                        .render()
+                        .unwrap(),
+                    )
+                }
+            }
+
+            if p.context.burn_after_read {
+                return Html(
+                    ErrorViewTemplate {
+                        config: database.config,
+                        error: DatabaseError::NotAllowed.to_string(),
+                    }
+                    .render()
+                    .unwrap(),
+                );
+            }
+
+            // ...
+            let diff = match database
+                .diff_post(&p, query_params.from, query_params.to)
+                .await
+            {
+                Ok(d) => d,
+                Err(e) => {
+                    return Html(
+                        ErrorViewTemplate {
+                            config: database.config,
+                            error: e.to_string(),
+                        }
+                        .render()
+                        .unwrap(),
+                    )
+                }
+            };
+
+            Html(
+                DiffTemplate {
+                    config: database.config,
+                    post: p,
+                    diff,
+                    split: query_params.mode == "split",
+                }
+                .render()
+                .unwrap(),
+            )
         }
         Err(e) => Html(
             ErrorViewTemplate {
@@ -198,7 +1072,10 @@ pub async fn view_post_request(
 struct EditorTemplate {
     config: Config,
     post: Post,
+    /// The token of the edit share link the editor was opened with
+    share: String,
     passwordless: bool,
+    is_admin: bool,
     is_powerful: bool,
 }
 
@@ -235,49 +1112,69 @@ pub async fn editor_request(
     // ...
     match database.get_post(slug).await {
         Ok(p) => {
+            let role = match auth_user {
+                Some(ref profile) => database.get_post_role(&p, profile),
+                None => None,
+            };
+
+            let share = match database.check_share(&p, &query_params.share).await {
+                Some(SharePermission::Edit) => query_params.share,
+                _ => String::new(),
+            };
+
+            let passwordless =
+                is_powerful | role.is_some_and(|r| r >= AclRole::Editor) | !share.is_empty();
+
+            // private posts are only viewable by their access list and share link holders
+            if !is_powerful && share.is_empty() && !database.can_view_post(&p, auth_user.as_deref())
+            {
+                return Html(
+                    ErrorViewTemplate {
+                        config: database.config,
+                        error: DatabaseError::NotFound.to_string(),
+                    }
+                    .render()
+                    .unwrap(),
+                );
+            }
+
             // check for view password
-            if database.config.view_password == true {
-                match query_params.view_password.is_empty() {
-                    false => {
-                        if !p.context.view_password.is_empty()
-                            && (query_params.view_password != p.context.view_password)
-                        {
-                            return Html(
-                                PostPasswordTemplate {
-                                    config: database.config,
-                                    post: p,
-                                }
-                                .render()
-                                .unwrap(),
-                            );
-                        }
+            if !is_powerful
+                && role.is_none()
+                && share.is_empty()
+                && !is_unlocked(&database, &jar, &p)
+            {
+                return Html(
+                    PostPasswordTemplate {
+                        config: database.config,
+                        next: format!("/{}/edit", p.slug),
+                        post: p,
                     }
-                    true => {
-                        if !p.context.view_password.is_empty() {
-                            return Html(
-                                PostPasswordTemplate {
-                                    config: database.config,
-                                    post: p,
-                                }
-                                .render()
-                                .unwrap(),
-                            );
-                        }
+                    .render()
+                    .unwrap(),
+                );
+            }
+
+            // only editors can see the content of a burn after read post before it's viewed
+            if p.context.burn_after_read && !passwordless {
+                return Html(
+                    ErrorViewTemplate {
+                        config: database.config,
+                        error: DatabaseError::NotAllowed.to_string(),
                     }
-                }
+                    .render()
+                    .unwrap(),
+                );
             }
 
             // ...
             Html(
                 EditorTemplate {
                     config: database.config,
-                    passwordless: is_powerful
-                        | if let Some(ref profile) = auth_user {
-                            profile.id == p.context.owner
-                        } else {
-                            false
-                        },
+                    passwordless,
                     post: p,
+                    share,
+                    is_admin: is_powerful | (role == Some(AclRole::Admin)),
                     is_powerful,
                 }
                 .render()
@@ -301,8 +1198,15 @@ struct ConfigEditorTemplate {
     config: Config,
     profile: Option<Box<Profile>>,
     post: Post,
+    revisions: Vec<PostRevision>,
     post_context: String,
+    has_view_password: bool,
+    /// (username, role)
+    acl: Vec<(String, AclRole)>,
+    /// (token, share)
+    shares: Vec<(String, Share)>,
     passwordless: bool,
+    is_admin: bool,
     is_powerful: bool,
 }
 
@@ -310,7 +1214,6 @@ pub async fn config_editor_request(
     jar: CookieJar,
     Path(slug): Path<String>,
     State(database): State<Database>,
-    Query(query_params): Query<PostViewQuery>,
 ) -> impl IntoResponse {
     // get auth token
     let auth_user = match jar.get("__Secure-Token") {
@@ -339,44 +1242,69 @@ pub async fn config_editor_request(
     // ...
     match database.get_post(slug).await {
         Ok(p) => {
-            // check for view password
-            if (database.config.view_password == true) && !is_powerful {
-                match query_params.view_password.is_empty() {
-                    false => {
-                        if !p.context.view_password.is_empty()
-                            && (query_params.view_password != p.context.view_password)
-                        {
-                            return Html(
-                                PostPasswordTemplate {
-                                    config: database.config,
-                                    post: p,
-                                }
-                                .render()
-                                .unwrap(),
-                            );
-                        }
+            let role = match auth_user {
+                Some(ref profile) => database.get_post_role(&p, profile),
+                None => None,
+            };
+
+            // private posts are only viewable by their access list
+            if !is_powerful && !database.can_view_post(&p, auth_user.as_deref()) {
+                return Html(
+                    ErrorViewTemplate {
+                        config: database.config,
+                        error: DatabaseError::NotFound.to_string(),
                     }
-                    true => {
-                        if !p.context.view_password.is_empty() {
-                            return Html(
-                                PostPasswordTemplate {
-                                    config: database.config,
-                                    post: p,
-                                }
-                                .render()
-                                .unwrap(),
-                            );
-                        }
+                    .render()
+                    .unwrap(),
+                );
+            }
+
+            // check for view password
+            if !is_powerful && role.is_none() && !is_unlocked(&database, &jar, &p) {
+                return Html(
+                    PostPasswordTemplate {
+                        config: database.config,
+                        next: format!("/{}/edit/config", p.slug),
+                        post: p,
                     }
+                    .render()
+                    .unwrap(),
+                );
+            }
+
+            // resolve the access list to usernames
+            let mut acl = Vec::new();
+
+            for (id, role) in &p.context.acl {
+                if let Ok(profile) = database.auth.get_profile(id.to_owned()).await {
+                    acl.push((profile.username, *role));
                 }
             }
 
+            // only post admins manage share links here
+            let shares = match auth_user {
+                Some(ref profile) if role == Some(AclRole::Admin) => database
+                    .get_shares_by_post(p.slug.clone(), profile.clone())
+                    .await
+                    .unwrap_or_default(),
+                _ => Vec::new(),
+            };
+
             // ...
             Html(
                 ConfigEditorTemplate {
                     config: database.config.clone(),
                     post: p.clone(),
-                    post_context: match serde_json::to_string(&p.context) {
+                    revisions: database
+                        .get_revisions_by_post(p.id.clone())
+                        .await
+                        .unwrap_or_default(),
+                    has_view_password: !p.context.view_password.is_empty(),
+                    post_context: match serde_json::to_string(&PostContext {
+                        // the view password is changed through its own field
+                        view_password: String::new(),
+                        ..p.context.clone()
+                    }) {
                         Ok(m) => m,
                         Err(_) => {
                             return Html(
@@ -389,12 +1317,10 @@ pub async fn config_editor_request(
                             )
                         }
                     },
-                    passwordless: is_powerful
-                        | if let Some(ref profile) = auth_user {
-                            profile.id == p.context.owner
-                        } else {
-                            false
-                        },
+                    acl,
+                    shares,
+                    passwordless: is_powerful | role.is_some_and(|r| r >= AclRole::Editor),
+                    is_admin: is_powerful | (role == Some(AclRole::Admin)),
                     profile: auth_user,
                     is_powerful,
                 }
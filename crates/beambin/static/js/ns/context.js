@@ -28,6 +28,57 @@
                     continue;
                 }
 
+                if (field[0] === "tags") {
+                    // suggestions complete the last tag, keeping the ones before it
+                    globalThis.suggest_tags = async (value) => {
+                        const tags = value.split(",").map((t) => t.trim());
+                        const last = tags.pop();
+
+                        const res = await (
+                            await fetch(
+                                `/api/v1/tags?q=${encodeURIComponent(last)}`,
+                            )
+                        ).json();
+
+                        if (res.success === false) {
+                            return;
+                        }
+
+                        document.getElementById("tag_suggestions").innerHTML =
+                            res.payload
+                                .map(
+                                    (t) =>
+                                        `<option value="${[...tags.filter((t) => t), t.tag].join(", ")}">${t.posts} posts</option>`,
+                                )
+                                .join("");
+                    };
+
+                    bind_to.innerHTML += `<div class="card secondary round flex justify-between items-center gap-2" style="flex-wrap: wrap;" id="field:${field[0]}">
+                        <label for="field_input:${field[0]}">${field[0]}</label>
+                        <input
+                          id="field_input:${field[0]}"
+                          type="text"
+                          list="tag_suggestions"
+                          placeholder="Comma separated"
+                          value="${field[1].join(", ")}"
+                          oninput="globalThis.suggest_tags(event.target.value)"
+                          onchange="globalThis.update_metadata_value('${field[0]}', event.target.value.split(',').map((t) => t.trim()).filter((t) => t))"
+                          style="width: max-content"
+                        />
+                        <datalist id="tag_suggestions"></datalist>
+                    </div>`;
+
+                    continue;
+                }
+
+                // the access list and view password have their own sections
+                if (
+                    typeof field[1] === "object" ||
+                    field[0] === "view_password"
+                ) {
+                    continue;
+                }
+
                 if (field[0] === "template") {
                     const paste_is_template = field[1] === "@";
                     const paste_source =
@@ -71,6 +122,55 @@
                     continue;
                 }
 
+                if (field[0] === "visibility") {
+                    bind_to.innerHTML += `<div class="card secondary round flex justify-between items-center gap-2" style="flex-wrap: wrap;" id="field:${field[0]}">
+                        <label for="field_input:${field[0]}">${field[0]}</label>
+                        <select
+                          id="field_input:${field[0]}"
+                          onchange="globalThis.update_metadata_value('${field[0]}', event.target.value)"
+                          style="width: max-content"
+                        >
+                            ${["Public", "Unlisted", "Private"]
+                                .map(
+                                    (v) =>
+                                        `<option value="${v}" ${field[1] === v ? "selected" : ""}>${v}</option>`,
+                                )
+                                .join("")}
+                        </select>
+                    </div>`;
+
+                    continue;
+                }
+
+                if (typeof field[1] === "boolean") {
+                    bind_to.innerHTML += `<div class="card secondary round flex justify-between items-center gap-2" style="flex-wrap: wrap;" id="field:${field[0]}">
+                        <label for="field_input:${field[0]}">${field[0]}</label>
+                        <input
+                          id="field_input:${field[0]}"
+                          type="checkbox"
+                          ${field[1] ? "checked" : ""}
+                          onchange="globalThis.update_metadata_value('${field[0]}', event.target.checked)"
+                        />
+                    </div>`;
+
+                    continue;
+                }
+
+                if (typeof field[1] === "number") {
+                    bind_to.innerHTML += `<div class="card secondary round flex justify-between items-center gap-2" style="flex-wrap: wrap;" id="field:${field[0]}">
+                        <label for="field_input:${field[0]}">${field[0]}</label>
+                        <input
+                          id="field_input:${field[0]}"
+                          type="number"
+                          value="${field[1]}"
+                          onchange="globalThis.update_metadata_value('${field[0]}', parseInt(event.target.value) || 0)"
+                          style="width: max-content"
+                        />
+                    </div>`;
+
+                    continue;
+                }
+
                 bind_to.innerHTML += `<div class="card secondary round flex justify-between items-center gap-2" style="flex-wrap: wrap;" id="field:${field[0]}">
                     <label for="field_input:${field[0]}">${field[0]}</label>
                     <input
@@ -101,6 +201,12 @@
                         body: JSON.stringify({
                             password: e.target.password.value,
                             context: $.context,
+                            new_view_password: (
+                                e.target.remove_view_password || {}
+                            ).checked
+                                ? ""
+                                : (e.target.new_view_password || {}).value ||
+                                  null,
                         }),
                     })
                 ).json();
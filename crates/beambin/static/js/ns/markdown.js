@@ -99,6 +99,9 @@
                             slug: e.target.slug.value,
                             password: e.target.password.value,
                             source: slug,
+                            captcha_response:
+                                e.target.querySelector(".h-captcha textarea")
+                                    ?.value || "",
                         }),
                     })
                 ).json();
@@ -108,7 +111,7 @@
 
                     $.dialog.close();
                 } else {
-                    window.location.href = `/${res.payload[1].slug}?ANNC=${res.payload[0]}`;
+                    window.location.href = `/${res.payload[1].slug}?ANNC=${res.payload[0] || "Post created"}`;
                 }
             });
     });